@@ -0,0 +1,318 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use serde::Deserialize;
+
+use crate::geom::Vec2;
+use crate::{AssetError, SheetRegion, Texture};
+
+/// What an entity is doing, as far as its animation is concerned. An
+/// [`AnimationSheet`] has (at most) one clip for each.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+pub enum AnimState {
+    Idle,
+    Walk,
+    Jump,
+    Fall,
+    Attack,
+    Hurt,
+    Dead,
+}
+
+impl AnimState {
+    /// Attacking, getting hurt and dying play through once before movement
+    /// gets to pick the state again.
+    pub fn is_action(&self) -> bool {
+        matches!(self, Self::Attack | Self::Hurt | Self::Dead)
+    }
+
+    // the state whose clip stands in when a sheet doesn't have this one
+    fn fallback(&self) -> Option<Self> {
+        match self {
+            Self::Idle => None,
+            Self::Fall => Some(Self::Jump),
+            _ => Some(Self::Idle),
+        }
+    }
+}
+
+/// One frame of a [`Clip`].
+#[derive(Clone, Debug, Deserialize)]
+pub struct Frame {
+    /// Where the frame is on its texture as (x, y, w, h), facing right.
+    pub rect: (u16, u16, u16, u16),
+    /// How many fixed updates it stays up for.
+    #[serde(default = "one")]
+    pub duration: usize,
+    /// Names the game can check for while this frame is showing, like `"hit"`
+    /// on the frames of an attack that can connect.
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+fn one() -> usize {
+    1
+}
+
+fn yes() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Clip {
+    /// Which of the sheet's textures the frames are on.
+    #[serde(default)]
+    pub texture: usize,
+    pub frames: Vec<Frame>,
+    /// Looping clips start over after the last frame; the others hold it.
+    #[serde(default = "yes")]
+    pub looping: bool,
+}
+
+// What's in an animation file; the textures are still paths
+#[derive(Debug, Deserialize)]
+pub(crate) struct AnimationFile {
+    pub textures: Vec<String>,
+    pub clips: HashMap<AnimState, Clip>,
+}
+
+impl AnimationFile {
+    pub fn load(path: &Path) -> Result<Self, AssetError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|err| AssetError::Io(path.to_path_buf(), err))?;
+        let file: Self = ron::from_str(&text)
+            .map_err(|err| AssetError::Parse(path.to_path_buf(), err.to_string()))?;
+        for (state, clip) in file.clips.iter() {
+            if clip.frames.is_empty() || clip.texture >= file.textures.len() {
+                return Err(AssetError::Parse(
+                    path.to_path_buf(),
+                    format!("{state:?} needs frames on one of the listed textures"),
+                ));
+            }
+        }
+        Ok(file)
+    }
+}
+
+/// A set of clips, loaded with [`Assets::animation`](crate::Assets::animation).
+pub struct AnimationSheet {
+    pub textures: Vec<Rc<Texture>>,
+    pub clips: HashMap<AnimState, Clip>,
+}
+
+impl AnimationSheet {
+    /// The clip for `state`, or the one standing in for it: falling looks like
+    /// jumping if there's no fall clip, and anything else without a clip looks
+    /// like idling.
+    pub fn clip(&self, state: AnimState) -> Option<&Clip> {
+        let mut state = Some(state);
+        while let Some(s) = state {
+            if let Some(clip) = self.clips.get(&s) {
+                return Some(clip);
+            }
+            state = s.fallback();
+        }
+        None
+    }
+}
+
+/// Plays an [`AnimationSheet`]'s clips for one entity, tracking which state
+/// it's in, the current frame and which way it faces. Call [`Animator::tick`]
+/// once per fixed update.
+pub struct Animator {
+    sheet: Rc<AnimationSheet>,
+    state: AnimState,
+    frame: usize,
+    // updates the current frame has been up
+    elapsed: usize,
+    finished: bool,
+    /// Draw the frames mirrored, facing left.
+    pub flipped: bool,
+}
+
+impl Animator {
+    pub fn new(sheet: Rc<AnimationSheet>) -> Self {
+        Self {
+            sheet,
+            state: AnimState::Idle,
+            frame: 0,
+            elapsed: 0,
+            finished: false,
+            flipped: false,
+        }
+    }
+
+    pub fn sheet(&self) -> &Rc<AnimationSheet> {
+        &self.sheet
+    }
+
+    /// Swap in a reloaded sheet, staying in the same state.
+    pub fn set_sheet(&mut self, sheet: Rc<AnimationSheet>) {
+        self.sheet = sheet;
+        let len = self.clip().map_or(1, |clip| clip.frames.len());
+        if self.frame >= len {
+            self.frame = len - 1;
+        }
+    }
+
+    pub fn state(&self) -> AnimState {
+        self.state
+    }
+
+    /// Index of the frame on screen within the current clip.
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Switch to `state`, starting its clip over unless it's already playing.
+    pub fn play(&mut self, state: AnimState) {
+        if state != self.state {
+            self.restart(state);
+        }
+    }
+
+    /// Start `state`'s clip from the first frame, even if it's already playing.
+    pub fn restart(&mut self, state: AnimState) {
+        self.state = state;
+        self.frame = 0;
+        self.elapsed = 0;
+        self.finished = false;
+    }
+
+    /// Pick idle, walk, jump or fall from how the entity is moving (y is up).
+    /// Does nothing while an action is still playing, or once it's dead.
+    pub fn follow_motion(&mut self, vel: Vec2, grounded: bool) {
+        if self.state == AnimState::Dead || (self.state.is_action() && !self.finished) {
+            return;
+        }
+        let state = if !grounded {
+            if vel.y > 0.0 {
+                AnimState::Jump
+            } else {
+                AnimState::Fall
+            }
+        } else if vel.x != 0.0 {
+            AnimState::Walk
+        } else {
+            AnimState::Idle
+        };
+        self.play(state);
+    }
+
+    /// Face right for positive `dir` and left for negative; zero keeps the
+    /// current facing.
+    pub fn face(&mut self, dir: f32) {
+        if dir != 0.0 {
+            self.flipped = dir < 0.0;
+        }
+    }
+
+    /// Advance one fixed update. Returns the events of the frame that just came
+    /// up, if the frame changed.
+    pub fn tick(&mut self) -> &[String] {
+        let Some(clip) = self.sheet.clip(self.state) else {
+            return &[];
+        };
+        if self.finished {
+            return &[];
+        }
+        self.elapsed += 1;
+        if self.elapsed < clip.frames[self.frame].duration {
+            return &[];
+        }
+        self.elapsed = 0;
+        if self.frame + 1 < clip.frames.len() {
+            self.frame += 1;
+        } else if clip.looping {
+            self.frame = 0;
+        } else {
+            self.finished = true;
+            return &[];
+        }
+        &clip.frames[self.frame].events
+    }
+
+    /// Whether a clip that doesn't loop has shown its last frame all the way through.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Whether the frame on screen has `event`.
+    pub fn has_event(&self, event: &str) -> bool {
+        self.current()
+            .is_some_and(|frame| frame.events.iter().any(|e| e == event))
+    }
+
+    /// Which of the sheet's textures the frame on screen is on.
+    pub fn texture(&self) -> usize {
+        self.clip().map_or(0, |clip| clip.texture)
+    }
+
+    /// Where the frame on screen is. `sheet` is the sprite group drawing from
+    /// that texture.
+    pub fn sheet_region(&self, sheet: u16, depth: u16) -> SheetRegion {
+        let Some(frame) = self.current() else {
+            return SheetRegion::new(sheet, 0, 0, depth, 0, 0);
+        };
+        let (x, y, w, h) = frame.rect;
+        let mut region = SheetRegion::new(sheet, x, y, depth, w, h);
+        if self.flipped {
+            // a negative width reads the frame right to left, from its right edge
+            region.x += w;
+            region.w = -region.w;
+        }
+        region
+    }
+
+    fn clip(&self) -> Option<&Clip> {
+        self.sheet.clip(self.state)
+    }
+
+    fn current(&self) -> Option<&Frame> {
+        self.clip().map(|clip| &clip.frames[self.frame])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn walker() -> Animator {
+        let frame = |x| Frame {
+            rect: (x, 16, 32, 48),
+            duration: 2,
+            events: Vec::default(),
+        };
+        let clip = Clip {
+            texture: 0,
+            frames: vec![frame(0), frame(32)],
+            looping: true,
+        };
+        Animator::new(Rc::new(AnimationSheet {
+            textures: Vec::default(),
+            clips: HashMap::from([(AnimState::Walk, clip)]),
+        }))
+    }
+
+    #[test]
+    fn facing_left_reads_the_frame_backwards() {
+        let mut anim = walker();
+        anim.play(AnimState::Walk);
+        anim.tick();
+        anim.tick();
+        assert_eq!(
+            anim.sheet_region(3, 1),
+            SheetRegion::new(3, 32, 16, 1, 32, 48)
+        );
+        anim.face(-1.0);
+        let flipped = anim.sheet_region(3, 1);
+        assert_eq!((flipped.x, flipped.w), (64, -32));
+        assert_eq!((flipped.y, flipped.h), (16, 48));
+        // no direction keeps the facing
+        anim.face(0.0);
+        assert_eq!(anim.sheet_region(3, 1), flipped);
+        anim.face(1.0);
+        assert_eq!(anim.sheet_region(3, 1).w, 32);
+    }
+}
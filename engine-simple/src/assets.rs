@@ -0,0 +1,259 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc;
+
+use crate::animation::{AnimationFile, AnimationSheet};
+use crate::{Renderer, Texture};
+
+/// Why an asset couldn't be loaded.
+#[derive(Debug)]
+pub enum AssetError {
+    Missing(PathBuf),
+    Io(PathBuf, std::io::Error),
+    Image(PathBuf, image::ImageError),
+    /// The file was read but its contents didn't make sense.
+    Parse(PathBuf, String),
+    Watch(notify::Error),
+}
+
+impl std::fmt::Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing(path) => write!(f, "asset not found: {}", path.display()),
+            Self::Io(path, err) => write!(f, "couldn't read {}: {err}", path.display()),
+            Self::Image(path, err) => write!(f, "couldn't decode image {}: {err}", path.display()),
+            Self::Parse(path, msg) => write!(f, "couldn't parse {}: {msg}", path.display()),
+            Self::Watch(err) => write!(f, "couldn't watch assets for changes: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+struct Watcher {
+    // kept alive so events keep arriving
+    _watcher: notify::RecommendedWatcher,
+    events: mpsc::Receiver<notify::Result<notify::Event>>,
+}
+
+/// Loads files from an asset directory once and hands out shared copies.
+///
+/// Textures are uploaded once per path no matter how many sprite groups use
+/// them. Anything else
+/// (levels, sounds, ...) goes through [`Assets::load`] with a parser of the
+/// game's choosing.
+///
+/// With [`Assets::watch`] on, changed textures are re-uploaded in place and
+/// other assets are dropped from the cache so the next `load` reads them fresh.
+/// [`Assets::take_changed`] tells the game which files those were.
+pub struct Assets {
+    root: PathBuf,
+    textures: HashMap<PathBuf, Rc<Texture>>,
+    cache: HashMap<(PathBuf, TypeId), Rc<dyn Any>>,
+    watcher: Option<Watcher>,
+    changed: Vec<PathBuf>,
+}
+
+impl Assets {
+    /// Paths passed to the other methods are relative to `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            textures: HashMap::default(),
+            cache: HashMap::default(),
+            watcher: None,
+            changed: Vec::default(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Where `path` lives on disk, or an error if nothing is there.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Result<PathBuf, AssetError> {
+        let full = self.root.join(path);
+        if full.exists() {
+            Ok(full)
+        } else {
+            Err(AssetError::Missing(full))
+        }
+    }
+
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> Result<String, AssetError> {
+        let full = self.resolve(path)?;
+        std::fs::read_to_string(&full).map_err(|err| AssetError::Io(full, err))
+    }
+
+    pub fn image(&self, path: impl AsRef<Path>) -> Result<image::RgbaImage, AssetError> {
+        let full = self.resolve(path)?;
+        image::open(&full)
+            .map(|img| img.into_rgba8())
+            .map_err(|err| AssetError::Image(full, err))
+    }
+
+    /// The texture for `path`, uploading it the first time it's asked for.
+    pub fn texture(
+        &mut self,
+        renderer: &mut Renderer,
+        path: impl AsRef<Path>,
+    ) -> Result<Rc<Texture>, AssetError> {
+        let full = self.resolve(path.as_ref())?;
+        if let Some(tex) = self.textures.get(&full) {
+            return Ok(tex.clone());
+        }
+        let img = self.image(path)?;
+        let tex = Rc::new(renderer.create_texture(&img, &full.to_string_lossy()));
+        self.textures.insert(full, tex.clone());
+        Ok(tex)
+    }
+
+    /// The asset at `path`, running `parse` on the file the first time it's asked
+    /// for (or the first time since it changed on disk).
+    pub fn load<T: 'static>(
+        &mut self,
+        path: impl AsRef<Path>,
+        parse: impl FnOnce(&Path) -> Result<T, AssetError>,
+    ) -> Result<Rc<T>, AssetError> {
+        let full = self.resolve(path)?;
+        let key = (full, TypeId::of::<T>());
+        if let Some(asset) = self.cache.get(&key) {
+            return Ok(asset.clone().downcast().unwrap());
+        }
+        let asset = Rc::new(parse(&key.0)?);
+        self.cache.insert(key, asset.clone());
+        Ok(asset)
+    }
+
+    /// The animation clips described by the RON file at `path`, along with the
+    /// textures they're drawn from.
+    pub fn animation(
+        &mut self,
+        renderer: &mut Renderer,
+        path: impl AsRef<Path>,
+    ) -> Result<Rc<AnimationSheet>, AssetError> {
+        let file = self.load(path, AnimationFile::load)?;
+        let textures = file
+            .textures
+            .iter()
+            .map(|tex| self.texture(renderer, tex))
+            .collect::<Result<_, _>>()?;
+        Ok(Rc::new(AnimationSheet {
+            textures,
+            clips: file.clips.clone(),
+        }))
+    }
+
+    /// Forget a cached asset so the next [`Assets::load`] reads it again.
+    pub fn invalidate(&mut self, path: impl AsRef<Path>) {
+        let full = self.root.join(path);
+        self.cache.retain(|(cached, _), _| *cached != full);
+    }
+
+    /// Start watching the asset directory for changes. See [`Assets::poll`].
+    pub fn watch(&mut self) -> Result<(), AssetError> {
+        use notify::Watcher as _;
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })
+        .map_err(AssetError::Watch)?;
+        watcher
+            .watch(&self.root, notify::RecursiveMode::Recursive)
+            .map_err(AssetError::Watch)?;
+        self.watcher = Some(Watcher {
+            _watcher: watcher,
+            events,
+        });
+        Ok(())
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.watcher.is_some()
+    }
+
+    /// Pick up files that changed on disk since the last poll: re-upload
+    /// textures and drop other cached assets. The engine calls this once per
+    /// frame before simulating. Changed paths stay queued until taken with
+    /// [`Assets::take_changed`], so frames with no update in them, or updates
+    /// that don't look, don't lose them.
+    pub fn poll(&mut self, renderer: &mut Renderer) {
+        let Some(watcher) = &self.watcher else {
+            return;
+        };
+        let mut touched: Vec<PathBuf> = Vec::default();
+        for event in watcher.events.try_iter() {
+            match event {
+                Ok(event) if event.kind.is_modify() || event.kind.is_create() => {
+                    for path in event.paths {
+                        let path = path.canonicalize().unwrap_or(path);
+                        if !touched.contains(&path) {
+                            touched.push(path);
+                        }
+                    }
+                }
+                Ok(_) => (),
+                Err(err) => eprintln!("{}", AssetError::Watch(err)),
+            }
+        }
+        if touched.is_empty() {
+            return;
+        }
+        let is_touched = |path: &Path| {
+            path.canonicalize()
+                .is_ok_and(|path| touched.contains(&path))
+        };
+        let textures: Vec<(PathBuf, Rc<Texture>)> = self
+            .textures
+            .iter()
+            .filter(|(path, _)| is_touched(path))
+            .map(|(path, tex)| (path.clone(), tex.clone()))
+            .collect();
+        for (path, tex) in textures {
+            // editors often save in several steps, so a half-written file isn't fatal
+            match image::open(&path) {
+                Ok(img) => {
+                    if renderer.update_texture(&tex, &img.into_rgba8()) {
+                        if !self.changed.contains(&path) {
+                            self.changed.push(path);
+                        }
+                    } else {
+                        eprintln!(
+                            "{} changed size; restart to see it at the new size",
+                            path.display()
+                        );
+                    }
+                }
+                Err(err) => eprintln!("{}", AssetError::Image(path, err)),
+            }
+        }
+        let stale: Vec<PathBuf> = self
+            .cache
+            .keys()
+            .map(|(path, _)| path.clone())
+            .filter(|path| is_touched(path))
+            .collect();
+        for path in stale {
+            self.cache.retain(|(cached, _), _| *cached != path);
+            if !self.changed.contains(&path) {
+                self.changed.push(path);
+            }
+        }
+    }
+
+    /// Assets that changed on disk and haven't been taken yet.
+    pub fn changed(&self) -> &[PathBuf] {
+        &self.changed
+    }
+
+    /// Whether `path` (relative to the asset root) changed on disk since it was
+    /// last taken. Taking it clears it, so each change is only acted on once.
+    pub fn take_changed(&mut self, path: impl AsRef<Path>) -> bool {
+        let full = self.root.join(path);
+        let before = self.changed.len();
+        self.changed.retain(|changed| *changed != full);
+        self.changed.len() != before
+    }
+}
@@ -0,0 +1,347 @@
+use crate::geom::{Vec2, AABB};
+use crate::Camera;
+
+/// A camera that follows a target around a level bigger than the screen.
+///
+/// The target can wander inside the dead zone without the camera moving; past
+/// it, the camera eases after the target, leading it in the direction it's
+/// moving, and never shows anything outside the bounds. Call
+/// [`FollowCamera::update`] once per fixed update and hand
+/// [`FollowCamera::camera`] to the renderer.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FollowCamera {
+    /// World-space size of the view.
+    pub size: Vec2,
+    /// Half the size of the box around the view's center that the target can
+    /// move in without the camera following.
+    pub dead_zone: Vec2,
+    /// How many updates' worth of the target's velocity to look ahead by.
+    pub look_ahead: f32,
+    /// How much of the way to its goal the camera moves each update, from 0
+    /// (never) to 1 (immediately).
+    pub smoothing: f32,
+    /// The view stays inside this region. Along an axis where the region is
+    /// smaller than the view, the view is centered on it instead.
+    pub bounds: Option<AABB>,
+    center: Vec2,
+    // where the dead zone is centered
+    focus: Vec2,
+    lead: Vec2,
+}
+
+impl FollowCamera {
+    /// A camera showing `size` of the world, centered on `center`, that sticks
+    /// to its target until the other fields are set.
+    pub fn new(center: Vec2, size: Vec2) -> Self {
+        Self {
+            size,
+            dead_zone: Vec2::ZERO,
+            look_ahead: 0.0,
+            smoothing: 1.0,
+            bounds: None,
+            center,
+            focus: center,
+            lead: Vec2::ZERO,
+        }
+    }
+
+    /// Move towards `target`, which is moving at `vel` per update.
+    pub fn update(&mut self, target: Vec2, vel: Vec2) {
+        let offset = target - self.focus;
+        let outside = (offset.abs() - self.dead_zone).max(Vec2::ZERO);
+        self.focus += outside * offset.signum();
+        self.lead += (vel * self.look_ahead - self.lead) * self.smoothing;
+        let goal = self.clamp(self.focus + self.lead);
+        self.center = self.clamp(self.center + (goal - self.center) * self.smoothing);
+    }
+
+    /// Jump straight to `target`, e.g. after a level change.
+    pub fn snap_to(&mut self, target: Vec2) {
+        self.focus = target;
+        self.lead = Vec2::ZERO;
+        self.center = self.clamp(target);
+    }
+
+    pub fn center(&self) -> Vec2 {
+        self.center
+    }
+
+    /// The part of the world on screen.
+    pub fn view(&self) -> AABB {
+        AABB {
+            center: self.center,
+            size: self.size,
+        }
+    }
+
+    pub fn camera(&self) -> Camera {
+        let corner = self.center - self.size / 2.0;
+        Camera {
+            screen_pos: [corner.x, corner.y],
+            screen_size: [self.size.x, self.size.y],
+        }
+    }
+
+    // Keep a view centered at `center` inside the bounds
+    fn clamp(&self, center: Vec2) -> Vec2 {
+        let Some(bounds) = self.bounds else {
+            return center;
+        };
+        let slack = ((bounds.size - self.size) / 2.0).max(Vec2::ZERO);
+        center.clamp(bounds.center - slack, bounds.center + slack)
+    }
+}
+
+/// The furthest [`CameraEffects::zoom_to`] will zoom out, showing this many
+/// times less detail than the base camera.
+pub const MIN_ZOOM: f32 = 0.01;
+
+/// Shake, zoom and pans layered over whatever camera the game is using.
+///
+/// Call [`CameraEffects::update`] once per fixed update and pass the base
+/// camera through [`CameraEffects::apply`] before handing it to the renderer.
+/// Shake comes from trauma: it grows with the square of the trauma, which
+/// drains away by itself, so small knocks barely register and big ones rattle.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CameraEffects {
+    /// How far the view can be thrown at full trauma, in world units.
+    pub max_shake: Vec2,
+    /// Trauma lost per update.
+    pub recovery: f32,
+    trauma: f32,
+    zoom: Tween,
+    pan: Option<Pan>,
+    ticks: u32,
+}
+
+// An eased pan from an old view to the base camera
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Pan {
+    from: Camera,
+    progress: Tween,
+}
+
+// A value easing from one number to another
+#[derive(Clone, Copy, PartialEq, Debug)]
+struct Tween {
+    from: f32,
+    to: f32,
+    elapsed: usize,
+    duration: usize,
+}
+
+impl Tween {
+    fn settled(value: f32) -> Self {
+        Self {
+            from: value,
+            to: value,
+            elapsed: 0,
+            duration: 0,
+        }
+    }
+
+    fn value(&self) -> f32 {
+        if self.elapsed >= self.duration {
+            return self.to;
+        }
+        let t = ease_in_out(self.elapsed as f32 / self.duration as f32);
+        self.from + (self.to - self.from) * t
+    }
+
+    fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    fn step(&mut self) {
+        self.elapsed = (self.elapsed + 1).min(self.duration);
+    }
+}
+
+// Slow at both ends, for 0 <= t <= 1
+fn ease_in_out(t: f32) -> f32 {
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Smooth wobble between -1 and 1; `seed` picks which one
+fn wobble(t: f32, seed: f32) -> f32 {
+    ((t * 0.9 + seed).sin() + (t * 2.3 + seed * 1.7).sin() * 0.5) / 1.5
+}
+
+impl Default for CameraEffects {
+    fn default() -> Self {
+        Self {
+            max_shake: Vec2::new(8.0, 8.0),
+            recovery: 1.0 / 60.0,
+            trauma: 0.0,
+            zoom: Tween::settled(1.0),
+            pan: None,
+            ticks: 0,
+        }
+    }
+}
+
+impl CameraEffects {
+    /// Shake the view harder; trauma is capped at 1.
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    /// Ease to `zoom` times magnification over `duration` updates; 1 is the
+    /// base camera's own size and 2 shows half as much in each direction.
+    /// Zooms below [`MIN_ZOOM`] (including zero or negative ones) are raised to it.
+    pub fn zoom_to(&mut self, zoom: f32, duration: usize) {
+        self.zoom = Tween {
+            from: self.zoom.value(),
+            to: zoom.max(MIN_ZOOM),
+            elapsed: 0,
+            duration,
+        };
+    }
+
+    /// The magnification right now.
+    pub fn zoom(&self) -> f32 {
+        self.zoom.value()
+    }
+
+    /// Ease from the view `from` over to the base camera over `duration` updates,
+    /// e.g. from the old room to the new one. Pass in the camera as it was
+    /// last shown ([`CameraEffects::apply`]'s result) to carry on seamlessly.
+    pub fn pan_from(&mut self, from: Camera, duration: usize) {
+        self.pan = Some(Pan {
+            from,
+            progress: Tween {
+                from: 0.0,
+                to: 1.0,
+                elapsed: 0,
+                duration,
+            },
+        });
+    }
+
+    pub fn is_panning(&self) -> bool {
+        self.pan.is_some()
+    }
+
+    /// Drop every effect at once.
+    pub fn clear(&mut self) {
+        *self = Self {
+            max_shake: self.max_shake,
+            recovery: self.recovery,
+            ..Self::default()
+        };
+    }
+
+    /// Advance one fixed update.
+    pub fn update(&mut self) {
+        self.ticks = self.ticks.wrapping_add(1);
+        self.trauma = (self.trauma - self.recovery).max(0.0);
+        self.zoom.step();
+        if let Some(pan) = &mut self.pan {
+            pan.progress.step();
+            if pan.progress.is_done() {
+                self.pan = None;
+            }
+        }
+    }
+
+    /// `base` with the effects applied.
+    pub fn apply(&self, base: Camera) -> Camera {
+        let size = Vec2::from(base.screen_size);
+        let mut center = Vec2::from(base.screen_pos) + size / 2.0;
+        let mut size = size / self.zoom.value();
+        if let Some(pan) = &self.pan {
+            let t = pan.progress.value();
+            let from_size = Vec2::from(pan.from.screen_size);
+            let from_center = Vec2::from(pan.from.screen_pos) + from_size / 2.0;
+            center = from_center.lerp(center, t);
+            size = from_size.lerp(size, t);
+        }
+        let shake = self.trauma * self.trauma;
+        let t = self.ticks as f32;
+        center += self.max_shake * shake * Vec2::new(wobble(t, 0.0), wobble(t, 10.0));
+        let corner = center - size / 2.0;
+        Camera {
+            screen_pos: [corner.x, corner.y],
+            screen_size: [size.x, size.y],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn follower() -> FollowCamera {
+        FollowCamera::new(Vec2::ZERO, Vec2::new(320.0, 240.0))
+    }
+
+    #[test]
+    fn the_dead_zone_holds_the_camera_until_the_target_leaves_it() {
+        let mut camera = follower();
+        camera.dead_zone = Vec2::new(16.0, 8.0);
+        camera.update(Vec2::new(10.0, -8.0), Vec2::ZERO);
+        assert_eq!(camera.center(), Vec2::ZERO);
+        camera.update(Vec2::new(20.0, -12.0), Vec2::ZERO);
+        assert_eq!(camera.center(), Vec2::new(4.0, -4.0));
+        // coming back inside leaves it where it was pushed to
+        camera.update(Vec2::new(0.0, 0.0), Vec2::ZERO);
+        assert_eq!(camera.center(), Vec2::new(4.0, -4.0));
+    }
+
+    #[test]
+    fn smoothing_eases_after_the_target_and_looks_ahead() {
+        let mut camera = follower();
+        camera.smoothing = 0.5;
+        camera.update(Vec2::new(100.0, 0.0), Vec2::ZERO);
+        assert_eq!(camera.center(), Vec2::new(50.0, 0.0));
+        camera.update(Vec2::new(100.0, 0.0), Vec2::ZERO);
+        assert_eq!(camera.center(), Vec2::new(75.0, 0.0));
+
+        let mut camera = follower();
+        camera.look_ahead = 10.0;
+        camera.update(Vec2::ZERO, Vec2::new(2.0, -1.0));
+        assert_eq!(camera.center(), Vec2::new(20.0, -10.0));
+    }
+
+    #[test]
+    fn the_view_stays_inside_the_bounds() {
+        let mut camera = follower();
+        camera.bounds = Some(AABB {
+            center: Vec2::new(400.0, 0.0),
+            size: Vec2::new(800.0, 200.0),
+        });
+        camera.snap_to(Vec2::new(-50.0, 90.0));
+        // pushed right against the left edge; too short, so centered vertically
+        assert_eq!(camera.center(), Vec2::new(160.0, 0.0));
+        camera.update(Vec2::new(1000.0, -90.0), Vec2::ZERO);
+        assert_eq!(camera.center(), Vec2::new(640.0, 0.0));
+        let view = camera.view();
+        assert_eq!(view.center.x + view.size.x / 2.0, 800.0);
+        assert_eq!(
+            camera.camera(),
+            Camera {
+                screen_pos: [480.0, -120.0],
+                screen_size: [320.0, 240.0],
+            }
+        );
+    }
+
+    #[test]
+    fn zooming_to_nothing_stays_finite() {
+        let base = follower().camera();
+        let mut effects = CameraEffects::default();
+        for zoom in [0.0, -2.0, f32::NAN] {
+            effects.zoom_to(zoom, 0);
+            assert_eq!(effects.zoom(), MIN_ZOOM);
+            let camera = effects.apply(base);
+            assert!(camera.screen_size.iter().all(|size| size.is_finite()));
+            assert!(camera.screen_pos.iter().all(|pos| pos.is_finite()));
+        }
+        effects.zoom_to(2.0, 0);
+        assert_eq!(effects.apply(base).screen_size, [160.0, 120.0]);
+    }
+}
@@ -1,5 +1,7 @@
 use frenderer::{GPUCamera, Transform};
 pub use glam::*;
+use std::collections::HashMap;
+use std::hash::Hash;
 
 #[repr(C)]
 #[derive(Clone, Copy, PartialEq, bytemuck::Zeroable, bytemuck::Pod, Debug)]
@@ -123,4 +125,1016 @@ impl AABB {
         }
     }
 
-}
\ No newline at end of file
+}
+/// How two overlapping boxes touch: moving the first box by `normal * depth`
+/// (the minimum translation vector) separates it from the second.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Contact {
+    /// Unit axis pointing away from the other box, e.g. `(0, 1)` when standing on it.
+    pub normal: Vec2,
+    /// How far the boxes overlap along `normal`.
+    pub depth: f32,
+}
+
+impl Contact {
+    /// The minimum translation vector.
+    pub fn mtv(&self) -> Vec2 {
+        self.normal * self.depth
+    }
+}
+
+impl Rect {
+    /// Signed contact with `other`, or `None` if they are apart or only touching.
+    /// The normal is along whichever axis overlaps least.
+    pub fn contact(&self, other: Rect) -> Option<Contact> {
+        let overlap = self.displacement(other)?;
+        if overlap.x <= 0.0 || overlap.y <= 0.0 {
+            return None;
+        }
+        let delta = (self.corner + self.size / 2.0) - (other.corner + other.size / 2.0);
+        // centers that line up exactly push up / right
+        let sign = |d: f32| if d < 0.0 { -1.0 } else { 1.0 };
+        if overlap.y <= overlap.x {
+            Some(Contact {
+                normal: Vec2::new(0.0, sign(delta.y)),
+                depth: overlap.y,
+            })
+        } else {
+            Some(Contact {
+                normal: Vec2::new(sign(delta.x), 0.0),
+                depth: overlap.x,
+            })
+        }
+    }
+
+    /// Area of the overlap with `other`, or 0 if they don't overlap.
+    pub fn overlap_area(&self, other: Rect) -> f32 {
+        self.displacement(other).map_or(0.0, |d| d.x.max(0.0) * d.y.max(0.0))
+    }
+}
+
+impl AABB {
+    pub fn contact(&self, other: AABB) -> Option<Contact> {
+        Rect::from(*self).contact(Rect::from(other))
+    }
+
+    pub fn overlap_area(&self, other: AABB) -> f32 {
+        Rect::from(*self).overlap_area(Rect::from(other))
+    }
+}
+
+/// Push `body` out of every box in `walls`, returning the index of each wall it
+/// was pushed out of along with the contact that was resolved.
+///
+/// Walls are handled biggest overlap first, and each one is re-tested after the
+/// pushes before it, so a body sliding across a row of tiles is lifted out by the
+/// tile it is mostly on and never catches on the seam with the next one.
+pub fn resolve_contacts(body: &mut AABB, walls: &[AABB]) -> Vec<(usize, Contact)> {
+    let mut order: Vec<(usize, f32)> = walls
+        .iter()
+        .enumerate()
+        .map(|(i, wall)| (i, body.overlap_area(*wall)))
+        .filter(|(_, area)| *area > 0.0)
+        .collect();
+    order.sort_by(|(_, a1), (_, a2)| a2.partial_cmp(a1).unwrap());
+    let mut resolved = Vec::with_capacity(order.len());
+    for (i, _) in order {
+        if let Some(contact) = body.contact(walls[i]) {
+            body.center += contact.mtv();
+            resolved.push((i, contact));
+        }
+    }
+    resolved
+}
+
+/// Where a moving box first touches a static one.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hit {
+    /// Fraction of the motion covered before touching, from 0 to 1.
+    pub time: f32,
+    /// Unit axis pointing away from the box that was hit.
+    pub normal: Vec2,
+}
+
+// how far (in world units) inside a slab counts as inside, so boxes resting
+// exactly on a surface can slide along it without catching on the next tile
+const SWEEP_SKIN: f32 = 1e-4;
+
+impl AABB {
+    /// When this box, moving by `motion`, would first touch `other`. Boxes that
+    /// already overlap are left to [`AABB::contact`] and don't count as hits.
+    pub fn sweep(&self, motion: Vec2, other: AABB) -> Option<Hit> {
+        if motion == Vec2::ZERO {
+            return None;
+        }
+        // the skin as a fraction of the motion, for comparing against times
+        let time_skin = SWEEP_SKIN / motion.length();
+        // sweep our center against `other` grown by our size
+        let min = other.center - (other.size + self.size) / 2.0;
+        let max = other.center + (other.size + self.size) / 2.0;
+        let mut entry = Vec2::splat(f32::NEG_INFINITY);
+        let mut exit = Vec2::splat(f32::INFINITY);
+        for axis in 0..2 {
+            let (c, m) = (self.center[axis], motion[axis]);
+            if m == 0.0 {
+                if c <= min[axis] + SWEEP_SKIN || c >= max[axis] - SWEEP_SKIN {
+                    return None;
+                }
+            } else {
+                let t1 = (min[axis] - c) / m;
+                let t2 = (max[axis] - c) / m;
+                entry[axis] = t1.min(t2);
+                exit[axis] = t1.max(t2);
+            }
+        }
+        let time = entry.x.max(entry.y);
+        if time > exit.x.min(exit.y) || !(-time_skin..=1.0).contains(&time) {
+            return None;
+        }
+        // moving along (or away from) a face we're exactly touching isn't a hit
+        if exit.x.min(exit.y) <= time_skin {
+            return None;
+        }
+        let normal = if entry.x > entry.y {
+            Vec2::new(-motion.x.signum(), 0.0)
+        } else {
+            Vec2::new(0.0, -motion.y.signum())
+        };
+        Some(Hit {
+            time: time.max(0.0),
+            normal,
+        })
+    }
+}
+
+/// The first of `walls` that `body` would touch moving by `motion`, if any.
+pub fn sweep_walls(body: AABB, motion: Vec2, walls: &[AABB]) -> Option<(usize, Hit)> {
+    walls
+        .iter()
+        .enumerate()
+        .filter_map(|(i, wall)| body.sweep(motion, *wall).map(|hit| (i, hit)))
+        .min_by(|(_, h1), (_, h2)| h1.time.partial_cmp(&h2.time).unwrap())
+}
+
+/// One wall that [`move_and_slide`] ran into.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SlideHit {
+    /// Index into the walls that were passed in.
+    pub wall: usize,
+    pub normal: Vec2,
+    /// Motion still to go when the wall was hit, before sliding along it.
+    pub remaining: Vec2,
+}
+
+/// Move `body` by `motion`, stopping at the first wall in the way and sliding
+/// along it with whatever motion is left. However fast the body moves it can't
+/// pass through a wall, no matter how thin.
+pub fn move_and_slide(body: &mut AABB, motion: Vec2, walls: &[AABB]) -> Vec<SlideHit> {
+    // a corner can take two hits; more than that means we're wedged
+    const MAX_SLIDES: usize = 4;
+    let mut hits = Vec::default();
+    let mut motion = motion;
+    for _ in 0..MAX_SLIDES {
+        if motion == Vec2::ZERO {
+            break;
+        }
+        let Some((wall, hit)) = sweep_walls(*body, motion, walls) else {
+            body.center += motion;
+            return hits;
+        };
+        body.center += motion * hit.time;
+        let remaining = motion * (1.0 - hit.time);
+        hits.push(SlideHit {
+            wall,
+            normal: hit.normal,
+            remaining,
+        });
+        // keep only the part of the motion that runs along the wall
+        motion = remaining - hit.normal * remaining.dot(hit.normal);
+    }
+    hits
+}
+
+/// Where a ray or segment first meets a box.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RayHit {
+    pub point: Vec2,
+    /// How far along the ray `point` is.
+    pub distance: f32,
+    /// Unit axis pointing away from the face that was hit, or zero if the ray
+    /// started inside the box.
+    pub normal: Vec2,
+}
+
+impl AABB {
+    /// The first point within `max_distance` of `origin` where a ray heading
+    /// along `dir` meets this box. A ray starting inside the box hits it
+    /// straight away. Rays that only run along a face, or start on one and
+    /// head away, don't hit.
+    pub fn raycast(&self, origin: Vec2, dir: Vec2, max_distance: f32) -> Option<RayHit> {
+        let dir = dir.try_normalize()?;
+        let min = self.center - self.size / 2.0;
+        let max = self.center + self.size / 2.0;
+        let mut entry = Vec2::splat(f32::NEG_INFINITY);
+        let mut exit = Vec2::splat(f32::INFINITY);
+        for axis in 0..2 {
+            let (o, d) = (origin[axis], dir[axis]);
+            if d == 0.0 {
+                if o <= min[axis] || o >= max[axis] {
+                    return None;
+                }
+            } else {
+                let t1 = (min[axis] - o) / d;
+                let t2 = (max[axis] - o) / d;
+                entry[axis] = t1.min(t2);
+                exit[axis] = t1.max(t2);
+            }
+        }
+        let t_entry = entry.x.max(entry.y);
+        let t_exit = exit.x.min(exit.y);
+        if t_entry > t_exit || t_exit <= 0.0 || t_entry > max_distance {
+            return None;
+        }
+        // starting on a face and heading in hits that face, below
+        if t_entry < 0.0 {
+            return Some(RayHit {
+                point: origin,
+                distance: 0.0,
+                normal: Vec2::ZERO,
+            });
+        }
+        let normal = if entry.x > entry.y {
+            Vec2::new(-dir.x.signum(), 0.0)
+        } else {
+            Vec2::new(0.0, -dir.y.signum())
+        };
+        Some(RayHit {
+            point: origin + dir * t_entry,
+            distance: t_entry,
+            normal,
+        })
+    }
+}
+
+/// The first of `walls` a ray from `origin` along `dir` meets within
+/// `max_distance`, with its index.
+pub fn raycast(
+    origin: Vec2,
+    dir: Vec2,
+    max_distance: f32,
+    walls: &[AABB],
+) -> Option<(usize, RayHit)> {
+    walls
+        .iter()
+        .enumerate()
+        .filter_map(|(i, wall)| wall.raycast(origin, dir, max_distance).map(|hit| (i, hit)))
+        .min_by(|(_, h1), (_, h2)| h1.distance.partial_cmp(&h2.distance).unwrap())
+}
+
+/// The first of `walls` the segment from `start` to `end` meets, with its index.
+pub fn segment_cast(start: Vec2, end: Vec2, walls: &[AABB]) -> Option<(usize, RayHit)> {
+    raycast(start, end - start, start.distance(end), walls)
+}
+
+/// Whether `to` can be seen from `from` without any of `walls` in the way.
+pub fn line_of_sight(from: Vec2, to: Vec2, walls: &[AABB]) -> bool {
+    segment_cast(from, to, walls).is_none()
+}
+
+/// A uniform grid of square cells for finding the boxes near a point or
+/// region without testing every box. Each box is stored under an id and
+/// listed in every cell it covers.
+pub struct SpatialGrid<Id> {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<Id>>,
+    boxes: HashMap<Id, AABB>,
+}
+
+impl<Id: Copy + Ord + Hash> SpatialGrid<Id> {
+    /// Cells a little bigger than the boxes that move around work best.
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "grid cells need a positive size");
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            boxes: HashMap::default(),
+        }
+    }
+    fn cell_range(&self, aabb: AABB) -> ((i32, i32), (i32, i32)) {
+        let min = (aabb.center - aabb.size / 2.0) / self.cell_size;
+        let max = (aabb.center + aabb.size / 2.0) / self.cell_size;
+        (
+            (min.x.floor() as i32, min.y.floor() as i32),
+            (max.x.floor() as i32, max.y.floor() as i32),
+        )
+    }
+    /// Add a box, replacing any box already stored under `id`.
+    pub fn insert(&mut self, id: Id, aabb: AABB) {
+        self.remove(id);
+        let ((x0, y0), (x1, y1)) = self.cell_range(aabb);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                self.cells.entry((x, y)).or_default().push(id);
+            }
+        }
+        self.boxes.insert(id, aabb);
+    }
+    /// Take a box out of the grid, returning where it was.
+    pub fn remove(&mut self, id: Id) -> Option<AABB> {
+        let aabb = self.boxes.remove(&id)?;
+        let ((x0, y0), (x1, y1)) = self.cell_range(aabb);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                if let Some(cell) = self.cells.get_mut(&(x, y)) {
+                    cell.retain(|other| *other != id);
+                    if cell.is_empty() {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+        Some(aabb)
+    }
+    /// Move a box that's already in the grid. Returns false if there is no box
+    /// stored under `id`.
+    pub fn relocate(&mut self, id: Id, aabb: AABB) -> bool {
+        let Some(old) = self.boxes.get(&id).copied() else {
+            return false;
+        };
+        if self.cell_range(old) == self.cell_range(aabb) {
+            self.boxes.insert(id, aabb);
+        } else {
+            self.insert(id, aabb);
+        }
+        true
+    }
+    pub fn get(&self, id: Id) -> Option<AABB> {
+        self.boxes.get(&id).copied()
+    }
+    pub fn len(&self) -> usize {
+        self.boxes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.boxes.clear();
+    }
+    /// Ids of the boxes overlapping or touching `region`, in ascending order.
+    pub fn query(&self, region: AABB) -> Vec<Id> {
+        let ((x0, y0), (x1, y1)) = self.cell_range(region);
+        let mut found = Vec::default();
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    found.extend(cell.iter().copied().filter(|id| {
+                        let aabb = self.boxes[id];
+                        let gap = (aabb.center - region.center).abs()
+                            - (aabb.size + region.size) / 2.0;
+                        gap.x <= 0.0 && gap.y <= 0.0
+                    }));
+                }
+            }
+        }
+        // boxes spanning several cells turn up more than once
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+    /// Ids of the boxes near the segment from `start` to `end`, in ascending
+    /// order; pair with [`segment_cast`] to find the one it actually hits.
+    pub fn query_segment(&self, start: Vec2, end: Vec2) -> Vec<Id> {
+        self.query(AABB {
+            center: (start + end) / 2.0,
+            size: (end - start).abs(),
+        })
+    }
+    /// Ids of the boxes containing `point`, in ascending order.
+    pub fn query_point(&self, point: Vec2) -> Vec<Id> {
+        self.query(AABB {
+            center: point,
+            size: Vec2::ZERO,
+        })
+    }
+}
+
+/// A circle, for saw blades and other round things.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Circle {
+    pub center: Vec2,
+    pub radius: f32,
+}
+
+/// A box turned `rot` radians counterclockwise about its center.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct OBB {
+    pub center: Vec2,
+    pub size: Vec2,
+    pub rot: f32,
+}
+
+/// A convex polygon, with its points in order around the edge (either way).
+/// With fewer than three points it has no inside, so it contains and overlaps nothing.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Polygon {
+    pub points: Vec<Vec2>,
+}
+
+/// Any of the collision shapes, so differently shaped things can be tested
+/// against each other.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Shape {
+    Box(AABB),
+    Circle(Circle),
+    Oriented(OBB),
+    Polygon(Polygon),
+}
+
+impl From<Circle> for Transform {
+    fn from(val: Circle) -> Self {
+        val.to_transform_rot(0.0)
+    }
+}
+
+impl From<OBB> for Transform {
+    fn from(val: OBB) -> Self {
+        Transform {
+            w: val.size.x as u16,
+            h: val.size.y as u16,
+            x: val.center.x,
+            y: val.center.y,
+            rot: val.rot,
+        }
+    }
+}
+
+impl From<&Polygon> for Transform {
+    fn from(val: &Polygon) -> Self {
+        val.bounds().into()
+    }
+}
+
+impl From<AABB> for OBB {
+    fn from(val: AABB) -> Self {
+        val.rotated(0.0)
+    }
+}
+
+impl From<OBB> for Polygon {
+    fn from(val: OBB) -> Self {
+        Polygon {
+            points: val.corners().to_vec(),
+        }
+    }
+}
+
+impl From<AABB> for Shape {
+    fn from(val: AABB) -> Self {
+        Shape::Box(val)
+    }
+}
+
+impl From<Circle> for Shape {
+    fn from(val: Circle) -> Self {
+        Shape::Circle(val)
+    }
+}
+
+impl From<OBB> for Shape {
+    fn from(val: OBB) -> Self {
+        Shape::Oriented(val)
+    }
+}
+
+impl From<Polygon> for Shape {
+    fn from(val: Polygon) -> Self {
+        Shape::Polygon(val)
+    }
+}
+
+impl AABB {
+    /// This box turned `rot` radians about its center, the same way
+    /// [`AABB::to_transform_rot`] draws it.
+    pub fn rotated(&self, rot: f32) -> OBB {
+        OBB {
+            center: self.center,
+            size: self.size,
+            rot,
+        }
+    }
+}
+
+impl Circle {
+    pub fn new(x: f32, y: f32, radius: f32) -> Self {
+        Circle {
+            center: Vec2::new(x, y),
+            radius,
+        }
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.center.distance_squared(point) <= self.radius * self.radius
+    }
+
+    pub fn bounds(&self) -> AABB {
+        AABB {
+            center: self.center,
+            size: Vec2::splat(self.radius * 2.0),
+        }
+    }
+
+    /// Draw the circle's bounding square turned by `rot`, e.g. for a spinning saw.
+    pub fn to_transform_rot(&self, rot: f32) -> Transform {
+        self.bounds().to_transform_rot(rot)
+    }
+}
+
+impl OBB {
+    /// Unit vectors along the box's own x and y axes.
+    pub fn axes(&self) -> [Vec2; 2] {
+        let x = Vec2::from_angle(self.rot);
+        [x, x.perp()]
+    }
+
+    /// Corners in counterclockwise order, starting at the bottom left.
+    pub fn corners(&self) -> [Vec2; 4] {
+        let [x, y] = self.axes();
+        let (hx, hy) = (x * self.size.x / 2.0, y * self.size.y / 2.0);
+        [
+            self.center - hx - hy,
+            self.center + hx - hy,
+            self.center + hx + hy,
+            self.center - hx + hy,
+        ]
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        let [x, y] = self.axes();
+        let offset = point - self.center;
+        offset.dot(x).abs() <= self.size.x / 2.0 && offset.dot(y).abs() <= self.size.y / 2.0
+    }
+
+    pub fn bounds(&self) -> AABB {
+        let [x, y] = self.axes();
+        let half = (x * self.size.x / 2.0).abs() + (y * self.size.y / 2.0).abs();
+        AABB {
+            center: self.center,
+            size: half * 2.0,
+        }
+    }
+}
+
+impl Polygon {
+    pub fn contains(&self, point: Vec2) -> bool {
+        if self.points.len() < 3 {
+            return false;
+        }
+        // inside means on the same side of every edge
+        let mut side = 0.0;
+        for (a, b) in edges(&self.points) {
+            let cross = (b - a).perp_dot(point - a);
+            if cross * side < 0.0 {
+                return false;
+            }
+            if cross != 0.0 {
+                side = cross;
+            }
+        }
+        true
+    }
+
+    pub fn bounds(&self) -> AABB {
+        let min = self.points.iter().fold(Vec2::INFINITY, |min, p| min.min(*p));
+        let max = self.points.iter().fold(Vec2::NEG_INFINITY, |max, p| max.max(*p));
+        AABB {
+            center: (min + max) / 2.0,
+            size: max - min,
+        }
+    }
+}
+
+// each point paired with the next, wrapping around
+fn edges(points: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b))
+}
+
+// smallest and largest distance along `axis` of any of `points`
+fn project(points: &[Vec2], axis: Vec2) -> (f32, f32) {
+    points.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), p| {
+        let d = p.dot(axis);
+        (min.min(d), max.max(d))
+    })
+}
+
+// separating axis test: two convex outlines overlap unless some edge normal
+// of either one splits them apart
+fn sat_overlap(points_a: &[Vec2], points_b: &[Vec2]) -> bool {
+    // with no edges to test, `all` below would say everything overlaps
+    if points_a.len() < 3 || points_b.len() < 3 {
+        return false;
+    }
+    edges(points_a).chain(edges(points_b)).all(|(a, b)| {
+        let axis = (b - a).perp();
+        let (min_a, max_a) = project(points_a, axis);
+        let (min_b, max_b) = project(points_b, axis);
+        min_a < max_b && min_b < max_a
+    })
+}
+
+fn circle_polygon_overlap(circle: Circle, points: &[Vec2]) -> bool {
+    if points.len() < 3 {
+        return false;
+    }
+    let Some(closest) = points
+        .iter()
+        .min_by(|a, b| {
+            let da = a.distance_squared(circle.center);
+            da.partial_cmp(&b.distance_squared(circle.center)).unwrap()
+        })
+        .copied()
+    else {
+        return false;
+    };
+    // the polygon's edge normals, plus the axis towards its nearest corner
+    let corner_axis = (circle.center - closest).try_normalize();
+    edges(points)
+        .filter_map(|(a, b)| (b - a).perp().try_normalize())
+        .chain(corner_axis)
+        .all(|axis| {
+            let (min, max) = project(points, axis);
+            let c = circle.center.dot(axis);
+            c - circle.radius < max && min < c + circle.radius
+        })
+}
+
+impl Shape {
+    pub fn contains(&self, point: Vec2) -> bool {
+        match self {
+            Shape::Box(aabb) => aabb.contains(point.x, point.y),
+            Shape::Circle(circle) => circle.contains(point),
+            Shape::Oriented(obb) => obb.contains(point),
+            Shape::Polygon(polygon) => polygon.contains(point),
+        }
+    }
+
+    /// The smallest [`AABB`] around the shape, e.g. for a [`SpatialGrid`].
+    pub fn bounds(&self) -> AABB {
+        match self {
+            Shape::Box(aabb) => *aabb,
+            Shape::Circle(circle) => circle.bounds(),
+            Shape::Oriented(obb) => obb.bounds(),
+            Shape::Polygon(polygon) => polygon.bounds(),
+        }
+    }
+
+    // corners of anything that isn't a circle
+    fn outline(&self) -> Option<Vec<Vec2>> {
+        match self {
+            Shape::Box(aabb) => Some(aabb.rotated(0.0).corners().to_vec()),
+            Shape::Circle(_) => None,
+            Shape::Oriented(obb) => Some(obb.corners().to_vec()),
+            Shape::Polygon(polygon) => Some(polygon.points.clone()),
+        }
+    }
+
+    /// Whether the two shapes overlap. Shapes that only touch don't.
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        match (self, other) {
+            (Shape::Circle(a), Shape::Circle(b)) => {
+                a.center.distance(b.center) < a.radius + b.radius
+            }
+            (Shape::Circle(circle), shape) | (shape, Shape::Circle(circle)) => {
+                circle_polygon_overlap(*circle, &shape.outline().unwrap())
+            }
+            (a, b) => sat_overlap(&a.outline().unwrap(), &b.outline().unwrap()),
+        }
+    }
+}
+
+/// Fit `camera` into a surface of `surface_size` pixels without stretching it.
+/// The camera's view stays centered, and whichever axis has room to spare is
+/// widened to fill the letterbox bars.
+pub fn letterbox(camera: GPUCamera, surface_size: Vec2) -> GPUCamera {
+    let size = Vec2::from(camera.screen_size);
+    if size.x <= 0.0 || size.y <= 0.0 || surface_size.x <= 0.0 || surface_size.y <= 0.0 {
+        return camera;
+    }
+    // world units per pixel, picked so the whole camera view fits on screen
+    let scale = (size.x / surface_size.x).max(size.y / surface_size.y);
+    let fitted = surface_size * scale;
+    GPUCamera {
+        screen_pos: (Vec2::from(camera.screen_pos) - (fitted - size) / 2.0).into(),
+        screen_size: fitted.into(),
+    }
+}
+
+/// Convert a position in physical window pixels (origin at the top left, like
+/// `Input::mouse_pos`) into world coordinates as seen through `camera`.
+pub fn screen_to_world(camera: GPUCamera, surface_size: Vec2, screen_pos: Vec2) -> Vec2 {
+    let fitted = letterbox(camera, surface_size);
+    let corner = Vec2::from(fitted.screen_pos);
+    let size = Vec2::from(fitted.screen_size);
+    Vec2 {
+        x: corner.x + screen_pos.x / surface_size.x * size.x,
+        y: corner.y + (1.0 - screen_pos.y / surface_size.y) * size.y,
+    }
+}
+
+/// The reverse of [`screen_to_world`]: where a world position shows up in
+/// physical window pixels.
+pub fn world_to_screen(camera: GPUCamera, surface_size: Vec2, world_pos: Vec2) -> Vec2 {
+    let fitted = letterbox(camera, surface_size);
+    let corner = Vec2::from(fitted.screen_pos);
+    let size = Vec2::from(fitted.screen_size);
+    Vec2 {
+        x: (world_pos.x - corner.x) / size.x * surface_size.x,
+        y: (1.0 - (world_pos.y - corner.y) / size.y) * surface_size.y,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Vec2, b: Vec2) -> bool {
+        (a - b).length() < 1e-3
+    }
+
+    fn tile(x: f32, y: f32) -> AABB {
+        AABB::new(x, y, 16.0, 16.0)
+    }
+
+    #[test]
+    fn contact_points_away_from_the_other_box() {
+        let contact = tile(0.0, 12.0).contact(tile(0.0, 0.0)).unwrap();
+        assert_eq!((contact.normal, contact.depth), (Vec2::new(0.0, 1.0), 4.0));
+        let contact = tile(-14.0, 2.0).contact(tile(0.0, 0.0)).unwrap();
+        assert_eq!((contact.normal, contact.depth), (Vec2::new(-1.0, 0.0), 2.0));
+        // just touching isn't a contact
+        assert!(tile(16.0, 0.0).contact(tile(0.0, 0.0)).is_none());
+    }
+
+    #[test]
+    fn sweep_finds_time_and_normal() {
+        let body = tile(0.0, 0.0);
+        let hit = body.sweep(Vec2::new(20.0, 0.0), tile(24.0, 0.0)).unwrap();
+        assert!((hit.time - 0.4).abs() < 1e-5);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+        // too short to reach it, or heading the other way
+        assert_eq!(body.sweep(Vec2::new(5.0, 0.0), tile(24.0, 0.0)), None);
+        assert_eq!(body.sweep(Vec2::new(-20.0, 0.0), tile(24.0, 0.0)), None);
+        // no motion, no hit
+        assert_eq!(body.sweep(Vec2::ZERO, tile(16.0, 0.0)), None);
+    }
+
+    #[test]
+    fn sweep_ignores_overlaps_and_faces_it_slides_along() {
+        let body = tile(0.0, 0.0);
+        // already overlapping is for contact() to sort out
+        assert_eq!(body.sweep(Vec2::new(4.0, 0.0), tile(8.0, 0.0)), None);
+        // standing on a floor and walking along it
+        let floor = tile(16.0, -16.0);
+        assert_eq!(body.sweep(Vec2::new(8.0, 0.0), floor), None);
+        // or jumping off it
+        assert_eq!(body.sweep(Vec2::new(0.0, 8.0), floor), None);
+        // but falling into it is a hit at time 0
+        let hit = body.sweep(Vec2::new(1.0, -4.0), floor).unwrap();
+        assert_eq!(hit.time, 0.0);
+        assert_eq!(hit.normal, Vec2::new(0.0, 1.0));
+    }
+
+    #[test]
+    fn move_and_slide_slides_along_floors_and_never_tunnels() {
+        let floor: Vec<AABB> = (0..8).map(|i| tile(i as f32 * 16.0, -16.0)).collect();
+        let mut body = tile(16.0, 0.0);
+        let hits = move_and_slide(&mut body, Vec2::new(30.0, -5.0), &floor);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].normal, Vec2::new(0.0, 1.0));
+        assert!(close(body.center, Vec2::new(46.0, 0.0)));
+
+        let thin_wall = [AABB::new(100.0, 0.0, 1.0, 64.0)];
+        let mut body = tile(0.0, 0.0);
+        move_and_slide(&mut body, Vec2::new(500.0, 0.0), &thin_wall);
+        assert!((body.center.x - 91.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn resolve_contacts_pushes_out_along_the_shallowest_axis() {
+        let mut body = tile(0.0, 12.0);
+        let resolved = resolve_contacts(&mut body, &[tile(0.0, 0.0), tile(40.0, 0.0)]);
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].0, 0);
+        assert_eq!(resolved[0].1.normal, Vec2::new(0.0, 1.0));
+        assert_eq!(body.center, Vec2::new(0.0, 16.0));
+
+        // standing across two floor tiles only gets pushed up once
+        let mut body = tile(8.0, 12.0);
+        let resolved = resolve_contacts(&mut body, &[tile(0.0, 0.0), tile(16.0, 0.0)]);
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(body.center, Vec2::new(8.0, 16.0));
+    }
+
+    #[test]
+    fn screen_to_world_sees_through_the_letterbox() {
+        let camera = GPUCamera {
+            screen_pos: [100.0, 50.0],
+            screen_size: [320.0, 240.0],
+        };
+        // a wider window than the camera, so it's letterboxed at the sides
+        let surface = Vec2::new(1280.0, 720.0);
+        assert!(close(
+            screen_to_world(camera, surface, Vec2::new(640.0, 360.0)),
+            Vec2::new(260.0, 170.0)
+        ));
+        // the top left of the picture is the camera's top left corner
+        assert!(close(
+            screen_to_world(camera, surface, Vec2::new(160.0, 0.0)),
+            Vec2::new(100.0, 290.0)
+        ));
+        assert!(close(
+            screen_to_world(camera, surface, Vec2::new(1120.0, 720.0)),
+            Vec2::new(420.0, 50.0)
+        ));
+    }
+
+    #[test]
+    fn world_to_screen_undoes_screen_to_world() {
+        let camera = GPUCamera {
+            screen_pos: [100.0, 50.0],
+            screen_size: [320.0, 240.0],
+        };
+        for surface in [Vec2::new(1280.0, 720.0), Vec2::new(300.0, 900.0)] {
+            let world = Vec2::new(180.0, 90.0);
+            let screen = world_to_screen(camera, surface, world);
+            assert!(close(screen_to_world(camera, surface, screen), world));
+        }
+    }
+
+    #[test]
+    fn grid_queries_find_nearby_boxes() {
+        let mut grid = SpatialGrid::new(32.0);
+        grid.insert(1, tile(8.0, 8.0));
+        grid.insert(2, tile(200.0, 8.0));
+        // spans four cells but comes back once
+        grid.insert(3, AABB::new(64.0, 64.0, 40.0, 40.0));
+        assert_eq!(grid.len(), 3);
+        assert_eq!(grid.query(tile(20.0, 8.0)), [1]);
+        assert_eq!(grid.query(AABB::new(64.0, 40.0, 200.0, 100.0)), [1, 3]);
+        assert_eq!(grid.query_point(Vec2::new(64.0, 64.0)), [3]);
+        assert_eq!(
+            grid.query_point(Vec2::new(120.0, 120.0)),
+            Vec::<i32>::default()
+        );
+        // touching counts
+        assert_eq!(grid.query(tile(24.0, 8.0)), [1]);
+    }
+
+    #[test]
+    fn grid_tracks_moves_and_removals() {
+        let mut grid = SpatialGrid::new(32.0);
+        grid.insert(1, tile(8.0, 8.0));
+        assert!(grid.relocate(1, tile(300.0, 8.0)));
+        assert!(grid.query_point(Vec2::new(8.0, 8.0)).is_empty());
+        assert_eq!(grid.query_point(Vec2::new(300.0, 8.0)), [1]);
+        assert!(!grid.relocate(2, tile(0.0, 0.0)));
+        assert_eq!(grid.remove(1), Some(tile(300.0, 8.0)));
+        assert!(grid.is_empty());
+        assert!(grid.query(AABB::new(0.0, 0.0, 1000.0, 1000.0)).is_empty());
+    }
+
+    #[test]
+    fn raycast_hits_the_near_face() {
+        let wall = tile(20.0, 0.0);
+        let hit = wall
+            .raycast(Vec2::ZERO, Vec2::new(1.0, 0.0), 100.0)
+            .unwrap();
+        assert_eq!(hit.point, Vec2::new(12.0, 0.0));
+        assert_eq!(hit.distance, 12.0);
+        assert_eq!(hit.normal, Vec2::new(-1.0, 0.0));
+        // out of range, or pointing away
+        assert_eq!(wall.raycast(Vec2::ZERO, Vec2::new(1.0, 0.0), 10.0), None);
+        assert_eq!(wall.raycast(Vec2::ZERO, Vec2::new(-1.0, 0.0), 100.0), None);
+        assert_eq!(wall.raycast(Vec2::ZERO, Vec2::ZERO, 100.0), None);
+    }
+
+    #[test]
+    fn raycast_from_inside_or_on_a_face() {
+        let wall = tile(0.0, 0.0);
+        let inside = wall.raycast(Vec2::new(1.0, 1.0), Vec2::Y, 10.0).unwrap();
+        assert_eq!((inside.distance, inside.normal), (0.0, Vec2::ZERO));
+        // on the top face: heading up misses, heading down hits the top
+        let top = Vec2::new(0.0, 8.0);
+        assert_eq!(wall.raycast(top, Vec2::Y, 10.0), None);
+        let hit = wall.raycast(top, -Vec2::Y, 10.0).unwrap();
+        assert_eq!((hit.distance, hit.normal), (0.0, Vec2::Y));
+        // running along the top face doesn't touch it
+        assert_eq!(wall.raycast(Vec2::new(-20.0, 8.0), Vec2::X, 40.0), None);
+    }
+
+    #[test]
+    fn segments_and_line_of_sight() {
+        let walls = [tile(50.0, 0.0), tile(30.0, 0.0), tile(30.0, 40.0)];
+        let (i, hit) = segment_cast(Vec2::ZERO, Vec2::new(100.0, 0.0), &walls).unwrap();
+        assert_eq!((i, hit.distance), (1, 22.0));
+        assert!(!line_of_sight(Vec2::ZERO, Vec2::new(100.0, 0.0), &walls));
+        assert!(line_of_sight(Vec2::ZERO, Vec2::new(20.0, 0.0), &walls));
+        assert!(line_of_sight(
+            Vec2::new(0.0, 20.0),
+            Vec2::new(100.0, 20.0),
+            &walls
+        ));
+    }
+
+    #[test]
+    fn grid_segment_queries_follow_the_segment() {
+        let mut grid = SpatialGrid::new(32.0);
+        grid.insert(1, tile(8.0, 8.0));
+        grid.insert(2, tile(200.0, 8.0));
+        grid.insert(3, tile(100.0, 200.0));
+        assert_eq!(
+            grid.query_segment(Vec2::new(0.0, 8.0), Vec2::new(300.0, 8.0)),
+            [1, 2]
+        );
+    }
+
+    fn triangle(x: f32, y: f32) -> Shape {
+        Polygon {
+            points: vec![
+                Vec2::new(x, y),
+                Vec2::new(x + 10.0, y),
+                Vec2::new(x, y + 10.0),
+            ],
+        }
+        .into()
+    }
+
+    #[test]
+    fn sat_polygons() {
+        assert!(triangle(0.0, 0.0).overlaps(&triangle(4.0, 4.0)));
+        // the second one's corner sits just past the first one's slanted edge
+        assert!(!triangle(0.0, 0.0).overlaps(&triangle(5.1, 5.1)));
+        // sharing an edge is only touching
+        assert!(!triangle(0.0, 0.0).overlaps(&triangle(10.0, 0.0)));
+        assert!(triangle(0.0, 0.0).contains(Vec2::new(2.0, 2.0)));
+        assert!(!triangle(0.0, 0.0).contains(Vec2::new(8.0, 8.0)));
+    }
+
+    #[test]
+    fn sat_polygons_against_circles_and_boxes() {
+        let tri = triangle(0.0, 0.0);
+        let near = Shape::from(Circle::new(6.0, 6.0, 2.0));
+        let far = Shape::from(Circle::new(10.0, 10.0, 2.0));
+        assert!(tri.overlaps(&near) && near.overlaps(&tri));
+        assert!(!tri.overlaps(&far));
+        // off the corner, where only the corner axis separates them
+        assert!(!tri.overlaps(&Circle::new(-2.0, -2.0, 2.0).into()));
+        // touching a flat edge
+        assert!(!tri.overlaps(&Circle::new(5.0, -2.0, 2.0).into()));
+
+        assert!(tri.overlaps(&AABB::new(0.0, 0.0, 4.0, 4.0).into()));
+        assert!(!tri.overlaps(&AABB::new(12.0, 5.0, 4.0, 4.0).into()));
+        // a box resting on the bottom edge
+        assert!(!tri.overlaps(&AABB::new(5.0, -2.0, 4.0, 4.0).into()));
+        // a box turned 45 degrees into the slanted edge
+        let diamond = AABB::new(6.0, 6.0, 4.0, 4.0).rotated(std::f32::consts::FRAC_PI_4);
+        assert!(tri.overlaps(&diamond.into()));
+    }
+
+    #[test]
+    fn sat_boxes_and_circles() {
+        let a: Shape = AABB::new(0.0, 0.0, 10.0, 10.0).into();
+        assert!(a.overlaps(&AABB::new(9.0, 0.0, 10.0, 10.0).into()));
+        assert!(!a.overlaps(&AABB::new(10.0, 0.0, 10.0, 10.0).into()));
+        let turned: Shape = AABB::new(10.5, 0.0, 10.0, 10.0).rotated(0.3).into();
+        assert!(a.overlaps(&turned));
+        let c: Shape = Circle::new(0.0, 0.0, 5.0).into();
+        assert!(c.overlaps(&Circle::new(9.0, 0.0, 5.0).into()));
+        assert!(!c.overlaps(&Circle::new(10.0, 0.0, 5.0).into()));
+        assert!(!a.overlaps(&Circle::new(10.0, 0.0, 5.0).into()));
+    }
+
+    #[test]
+    fn degenerate_polygons_overlap_nothing() {
+        let empty: Shape = Polygon { points: vec![] }.into();
+        let point: Shape = Polygon {
+            points: vec![Vec2::ZERO],
+        }
+        .into();
+        let line: Shape = Polygon {
+            points: vec![Vec2::new(-5.0, 0.0), Vec2::new(5.0, 0.0)],
+        }
+        .into();
+        let big: Shape = AABB::new(0.0, 0.0, 100.0, 100.0).into();
+        for shape in [&empty, &point, &line] {
+            assert!(!shape.overlaps(&big));
+            assert!(!big.overlaps(shape));
+            assert!(!shape.overlaps(&Circle::new(0.0, 0.0, 50.0).into()));
+            assert!(!shape.overlaps(&triangle(-1.0, -1.0)));
+            assert!(!shape.contains(Vec2::ZERO));
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use winit::event::MouseButton;
+
+use crate::{Engine, Game, InputEvent, Key};
+
+/// Drives a [`Game`] without a window or GPU, one fixed step at a time.
+///
+/// Input is injected as if it came from winit, and every step renders into the
+/// engine's headless renderer so tests can inspect what was uploaded.
+pub struct Headless<G: Game> {
+    pub engine: Engine,
+    pub game: G,
+}
+
+impl<G: Game> Headless<G> {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self::with_engine(Engine::headless(width, height))
+    }
+
+    /// Wrap an engine that was already set up, e.g. with [`Engine::replay_from`].
+    pub fn with_engine(mut engine: Engine) -> Self {
+        let game = G::new(&mut engine);
+        Self { engine, game }
+    }
+
+    pub fn press_key(&mut self, key: Key) {
+        self.send(InputEvent::Key { key, pressed: true });
+    }
+
+    pub fn release_key(&mut self, key: Key) {
+        self.send(InputEvent::Key {
+            key,
+            pressed: false,
+        });
+    }
+
+    pub fn press_mouse(&mut self, button: MouseButton) {
+        self.send(InputEvent::Mouse {
+            button,
+            pressed: true,
+        });
+    }
+
+    pub fn release_mouse(&mut self, button: MouseButton) {
+        self.send(InputEvent::Mouse {
+            button,
+            pressed: false,
+        });
+    }
+
+    /// Move the cursor to a position in physical window pixels.
+    pub fn move_mouse(&mut self, x: f64, y: f64) {
+        self.send(InputEvent::MouseMove { x, y });
+    }
+
+    /// Run `frames` fixed updates of [`crate::DT`] seconds, rendering after each one.
+    pub fn step(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.engine.assets.poll(&mut self.engine.renderer);
+            self.engine.simulate(&mut self.game);
+            self.game.render(&mut self.engine);
+            self.engine.renderer.render();
+        }
+    }
+
+    fn send(&mut self, event: InputEvent) {
+        self.engine.handle_input_event(&event.to_winit());
+    }
+}
@@ -1,8 +1,25 @@
+pub use animation::{AnimState, AnimationSheet, Animator, Clip, Frame};
+pub use assets::{AssetError, Assets};
 pub use bytemuck::Zeroable;
+pub use camera::{CameraEffects, FollowCamera, MIN_ZOOM};
 pub use frenderer::{
     input::{Input, Key},
     wgpu, BitFont, Frenderer, GPUCamera as Camera, SheetRegion, Transform,
 };
+pub use headless::Headless;
+pub use rand;
+pub use render::{Renderer, Texture};
+pub use replay::{InputEvent, Recording};
+pub use scene::{Scene, SceneStack, Transition};
+pub use sprites::{Sprite, SpriteGroup, SpriteRange};
+pub use timer::{Callback, Scheduler, TaskId, Timer};
+
+use rand::SeedableRng;
+use replay::{Playback, Recorder};
+
+/// Length of one fixed simulation step in seconds.
+pub const DT: f32 = 1.0 / 60.0;
+
 pub trait Game: Sized + 'static {
     fn new(engine: &mut Engine) -> Self;
     fn update(&mut self, engine: &mut Engine);
@@ -10,33 +27,146 @@ pub trait Game: Sized + 'static {
 }
 
 pub struct Engine {
-    pub renderer: Frenderer,
+    pub renderer: Renderer,
     pub input: Input,
+    /// Textures, levels, sounds etc. loaded from the `content` directory.
+    pub assets: Assets,
+    /// Seeded RNG for game logic; use this instead of `thread_rng` so replays match.
+    pub rng: rand::rngs::StdRng,
+    seed: u64,
+    time: f64,
+    recorder: Option<Recorder>,
+    playback: Option<Playback>,
     event_loop: Option<winit::event_loop::EventLoop<()>>,
-    window: winit::window::Window,
+    window: Option<winit::window::Window>,
 }
 
 impl Engine {
     pub fn new(builder: winit::window::WindowBuilder) -> Self {
         let event_loop = winit::event_loop::EventLoop::new();
         let window = builder.build(&event_loop).unwrap();
-        let renderer = frenderer::with_default_runtime(&window);
-        let input = Input::default();
+        let renderer = Renderer::gpu(frenderer::with_default_runtime(&window));
+        Self::with_renderer(renderer, Some(window), Some(event_loop))
+    }
+    /// An engine with no window or GPU; sprites are recorded instead of drawn.
+    /// Drive it with [`Headless`] rather than [`Engine::run`].
+    pub fn headless(width: u32, height: u32) -> Self {
+        Self::with_renderer(Renderer::headless(width, height), None, None)
+    }
+    fn with_renderer(
+        renderer: Renderer,
+        window: Option<winit::window::Window>,
+        event_loop: Option<winit::event_loop::EventLoop<()>>,
+    ) -> Self {
+        let seed = rand::random();
         Self {
             renderer,
-            input,
+            input: Input::default(),
+            assets: Assets::new("content"),
+            rng: rand::rngs::StdRng::seed_from_u64(seed),
+            seed,
+            time: 0.0,
+            recorder: None,
+            playback: None,
+            event_loop,
             window,
-            event_loop: Some(event_loop),
+        }
+    }
+    /// Record the RNG seed, the window size and every fixed step's input to
+    /// `path`. Steps are buffered and written out when the engine exits, on
+    /// [`Engine::flush_recording`], or if writing one fails.
+    /// Call before [`Engine::run`] so that `Game::new` sees the recorded seed.
+    pub fn record_to(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        self.rng = rand::rngs::StdRng::seed_from_u64(self.seed);
+        let window_size = self.renderer.surface_size();
+        self.recorder = Some(Recorder::new(path.as_ref(), self.seed, window_size)?);
+        Ok(())
+    }
+    /// Replay a session saved by [`Engine::record_to`]. Live input is ignored
+    /// until the recording runs out.
+    pub fn replay_from(&mut self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let recording = Recording::load(path)?;
+        self.seed = recording.seed;
+        self.rng = rand::rngs::StdRng::seed_from_u64(recording.seed);
+        self.playback = Some(Playback::new(recording));
+        Ok(())
+    }
+    pub fn flush_recording(&mut self) -> std::io::Result<()> {
+        match self.recorder.as_mut() {
+            Some(recorder) => recorder.flush(),
+            None => Ok(()),
+        }
+    }
+    /// Where the mouse is in world coordinates, through the renderer's current camera.
+    pub fn mouse_world_pos(&self) -> geom::Vec2 {
+        let mouse = self.input.mouse_pos();
+        geom::screen_to_world(
+            self.renderer.camera(),
+            self.surface_size(),
+            geom::Vec2::new(mouse.x as f32, mouse.y as f32),
+        )
+    }
+    fn surface_size(&self) -> geom::Vec2 {
+        let (w, h) = self.renderer.surface_size();
+        geom::Vec2::new(w as f32, h as f32)
+    }
+    /// Seconds of simulation time so far, i.e. fixed steps taken times `DT`.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+    pub fn is_replaying(&self) -> bool {
+        self.playback.is_some()
+    }
+    pub(crate) fn handle_input_event(&mut self, event: &winit::event::Event<()>) {
+        // recorded input wins while a replay is running
+        if self.playback.is_some() {
+            return;
+        }
+        if let (Some(recorder), Some(input)) =
+            (self.recorder.as_mut(), InputEvent::from_winit(event))
+        {
+            recorder.record(input);
+        }
+        self.input.process_input_event(event);
+    }
+    /// Simulate one fixed step of `DT` seconds.
+    pub(crate) fn simulate<G: Game>(&mut self, game: &mut G) {
+        let (camera, surface_size) = (self.renderer.camera(), self.surface_size());
+        if let Some(playback) = self.playback.as_mut() {
+            match playback.next_frame(camera, surface_size) {
+                Some(events) => {
+                    for event in events {
+                        self.input.process_input_event(&event.to_winit());
+                    }
+                }
+                None => {
+                    println!("replay finished, resuming live input");
+                    self.playback = None;
+                }
+            }
+        }
+        self.time += DT as f64;
+        game.update(self);
+        self.input.next_frame();
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(err) = recorder.end_frame() {
+                // dropping the recorder flushes whatever made it into the buffer
+                eprintln!("couldn't write input recording, so stopped recording: {err}");
+                self.recorder = None;
+            }
         }
     }
     pub fn run<G: Game>(mut self) {
         let mut game = G::new(&mut self);
-        const DT: f32 = 1.0 / 60.0;
         const DT_FUDGE_AMOUNT: f32 = 0.0002;
         const DT_MAX: f32 = DT * 5.0;
         const TIME_SNAPS: [f32; 5] = [15.0, 30.0, 60.0, 120.0, 144.0];
         let mut acc = 0.0;
         let mut now = std::time::Instant::now();
+        let window = self
+            .window
+            .take()
+            .expect("Engine::run needs a window; drive headless engines with Headless");
         self.event_loop
             .take()
             .unwrap()
@@ -50,6 +180,11 @@ impl Engine {
                     } => {
                         *control_flow = winit::event_loop::ControlFlow::Exit;
                     }
+                    Event::LoopDestroyed => {
+                        if let Err(err) = self.flush_recording() {
+                            eprintln!("couldn't save input recording: {err}");
+                        }
+                    }
                     Event::MainEventsCleared => {
                         // compute elapsed time since last frame
                         let mut elapsed = now.elapsed().as_secs_f32();
@@ -67,12 +202,13 @@ impl Engine {
                         }
                         acc += elapsed;
                         now = std::time::Instant::now();
+                        // swap in anything edited on disk before the game looks at it
+                        self.assets.poll(&mut self.renderer);
                         // While we have time to spend
                         while acc >= DT {
                             // simulate a frame
                             acc -= DT;
-                            game.update(&mut self);
-                            self.input.next_frame();
+                            self.simulate(&mut game);
                         }
                         game.render(&mut self);
                         // Render prep
@@ -81,16 +217,25 @@ impl Engine {
                         // ok now render.
                         // We could just call frend.render().
                         self.renderer.render();
-                        self.window.request_redraw();
+                        window.request_redraw();
                     }
                     event => {
                         if self.renderer.process_window_event(&event) {
-                            self.window.request_redraw();
+                            window.request_redraw();
                         }
-                        self.input.process_input_event(&event);
+                        self.handle_input_event(&event);
                     }
                 }
             });
     }
 }
-pub mod geom;
\ No newline at end of file
+mod animation;
+mod assets;
+mod camera;
+pub mod geom;
+mod headless;
+mod render;
+mod replay;
+mod scene;
+mod sprites;
+mod timer;
@@ -0,0 +1,384 @@
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::geom::{letterbox, Vec2};
+use crate::sprites::{Sprite, SpriteGroup, SpriteRange, SpriteSlots};
+use crate::{wgpu, BitFont, Camera, Frenderer, SheetRegion, Transform, Zeroable};
+
+/// A texture that sprite groups can draw from. Headless engines only keep the size.
+pub struct Texture {
+    gpu: Option<wgpu::Texture>,
+    size: (u32, u32),
+}
+
+impl Texture {
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+}
+
+// Stand-in for a GPU sprite group: the working copy the game writes into, plus
+// whatever was last uploaded so tests can see what would have been drawn.
+struct HeadlessGroup {
+    trfs: Vec<Transform>,
+    uvs: Vec<SheetRegion>,
+    uploaded_trfs: Vec<Transform>,
+    uploaded_uvs: Vec<SheetRegion>,
+}
+
+enum Backend {
+    Gpu(Frenderer),
+    Headless {
+        size: (u32, u32),
+        groups: Vec<HeadlessGroup>,
+        frames: usize,
+    },
+}
+
+/// Sprite rendering for the engine, either through frenderer or recorded in memory.
+pub struct Renderer {
+    backend: Backend,
+    // the camera the game asked for, before it was fitted to the surface
+    camera: Camera,
+    slots: Rc<RefCell<SpriteSlots>>,
+}
+
+impl Renderer {
+    pub(crate) fn gpu(frend: Frenderer) -> Self {
+        Self {
+            backend: Backend::Gpu(frend),
+            camera: Camera::zeroed(),
+            slots: Rc::default(),
+        }
+    }
+
+    pub(crate) fn headless(width: u32, height: u32) -> Self {
+        Self {
+            backend: Backend::Headless {
+                size: (width, height),
+                groups: Vec::default(),
+                frames: 0,
+            },
+            camera: Camera::zeroed(),
+            slots: Rc::default(),
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        matches!(self.backend, Backend::Headless { .. })
+    }
+
+    /// Size of the drawing surface in physical pixels.
+    pub fn surface_size(&self) -> (u32, u32) {
+        match &self.backend {
+            Backend::Gpu(frend) => (frend.gpu.config.width, frend.gpu.config.height),
+            Backend::Headless { size, .. } => *size,
+        }
+    }
+
+    pub fn create_texture(&mut self, img: &image::RgbaImage, label: &str) -> Texture {
+        let gpu = match &self.backend {
+            Backend::Gpu(frend) => Some(frend.gpu.create_texture(
+                img,
+                wgpu::TextureFormat::Rgba8UnormSrgb,
+                img.dimensions(),
+                Some(label),
+            )),
+            Backend::Headless { .. } => None,
+        };
+        Texture {
+            gpu,
+            size: img.dimensions(),
+        }
+    }
+
+    /// Replace a texture's pixels, e.g. after the image changed on disk. Returns
+    /// false (and leaves the texture alone) if the new image is a different size.
+    pub fn update_texture(&mut self, tex: &Texture, img: &image::RgbaImage) -> bool {
+        if img.dimensions() != tex.size {
+            return false;
+        }
+        if let (Backend::Gpu(frend), Some(texture)) = (&self.backend, &tex.gpu) {
+            let (width, height) = tex.size;
+            frend.gpu.queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                img,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(4 * width),
+                    rows_per_image: Some(height),
+                },
+                wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+        true
+    }
+
+    /// A new sprite group drawing from `tex`, starting with the given sprites.
+    /// It can start empty: [`Renderer::alloc_sprites`] grows it as slots are
+    /// handed out.
+    pub fn add_sprite_group(
+        &mut self,
+        tex: &Texture,
+        mut trfs: Vec<Transform>,
+        mut uvs: Vec<SheetRegion>,
+        camera: Camera,
+    ) -> SpriteGroup {
+        self.camera = camera;
+        let fitted = self.fitted_camera();
+        self.slots.borrow_mut().add_group();
+        let index = match &mut self.backend {
+            Backend::Gpu(frend) => {
+                // wgpu won't bind an empty buffer, so GPU groups hold at least one sprite
+                if trfs.is_empty() {
+                    trfs.push(Transform::zeroed());
+                    uvs.push(SheetRegion::zeroed());
+                }
+                frend.sprites.add_sprite_group(
+                    &frend.gpu,
+                    tex.gpu
+                        .as_ref()
+                        .expect("texture was created by a headless renderer"),
+                    trfs,
+                    uvs,
+                    fitted,
+                )
+            }
+            Backend::Headless { groups, .. } => {
+                groups.push(HeadlessGroup {
+                    uploaded_trfs: trfs.clone(),
+                    uploaded_uvs: uvs.clone(),
+                    trfs,
+                    uvs,
+                });
+                groups.len() - 1
+            }
+        };
+        SpriteGroup(index)
+    }
+
+    pub fn sprite_group_size(&self, which: SpriteGroup) -> usize {
+        match &self.backend {
+            Backend::Gpu(frend) => frend.sprites.sprite_group_size(which.0),
+            Backend::Headless { groups, .. } => groups[which.0].trfs.len(),
+        }
+    }
+
+    pub fn resize_sprite_group(&mut self, which: SpriteGroup, len: usize) -> usize {
+        match &mut self.backend {
+            Backend::Gpu(frend) => frend.sprites.resize_sprite_group(&frend.gpu, which.0, len),
+            Backend::Headless { groups, .. } => {
+                let group = &mut groups[which.0];
+                group.trfs.resize(len, Transform::zeroed());
+                group.uvs.resize(len, SheetRegion::zeroed());
+                group.uploaded_trfs.resize(len, Transform::zeroed());
+                group.uploaded_uvs.resize(len, SheetRegion::zeroed());
+                len
+            }
+        }
+    }
+
+    pub fn get_sprites_mut(
+        &mut self,
+        which: SpriteGroup,
+    ) -> (&mut [Transform], &mut [SheetRegion]) {
+        match &mut self.backend {
+            Backend::Gpu(frend) => frend.sprites.get_sprites_mut(which.0),
+            Backend::Headless { groups, .. } => {
+                let group = &mut groups[which.0];
+                (&mut group.trfs, &mut group.uvs)
+            }
+        }
+    }
+
+    pub fn upload_sprites(&mut self, which: SpriteGroup, range: Range<usize>) {
+        match &mut self.backend {
+            Backend::Gpu(frend) => frend.sprites.upload_sprites(&frend.gpu, which.0, range),
+            Backend::Headless { groups, .. } => {
+                let group = &mut groups[which.0];
+                group.uploaded_trfs[range.clone()].copy_from_slice(&group.trfs[range.clone()]);
+                group.uploaded_uvs[range.clone()].copy_from_slice(&group.uvs[range]);
+            }
+        }
+    }
+
+    /// Reserve `len` sprite slots in a group, growing the group if it is full.
+    /// The slots start out blank and are freed when the handle is dropped.
+    pub fn alloc_sprites(&mut self, group: SpriteGroup, len: usize) -> SpriteRange {
+        // blank anything freed since the last upload before it can be handed out again
+        self.clear_released();
+        let (range, end) = {
+            let mut slots = self.slots.borrow_mut();
+            let range = slots.alloc(group.0, len);
+            (range, slots.end(group.0))
+        };
+        let size = self.sprite_group_size(group);
+        if end > size {
+            self.resize_sprite_group(group, end.max(size * 2));
+        }
+        let (trfs, uvs) = self.get_sprites_mut(group);
+        trfs[range.clone()].fill(Transform::zeroed());
+        uvs[range.clone()].fill(SheetRegion::zeroed());
+        self.slots.borrow_mut().mark_dirty(group.0, range.clone());
+        SpriteRange::new(group, range, self.slots.clone())
+    }
+
+    pub fn alloc_sprite(&mut self, group: SpriteGroup) -> Sprite {
+        Sprite(self.alloc_sprites(group, 1))
+    }
+
+    /// Grow or shrink a range, keeping the sprites that fit. The range may move.
+    pub fn resize_sprites(&mut self, sprites: &mut SpriteRange, len: usize) {
+        if len == sprites.len() {
+            return;
+        }
+        let resized = self.alloc_sprites(sprites.group(), len);
+        let keep = len.min(sprites.len());
+        let old = sprites.range().start;
+        let new = resized.range().start;
+        let (trfs, uvs) = self.get_sprites_mut(sprites.group());
+        trfs.copy_within(old..old + keep, new);
+        uvs.copy_within(old..old + keep, new);
+        // the old slots are freed as `resized` takes their place
+        *sprites = resized;
+    }
+
+    /// The sprites behind a handle. They are uploaded before the next frame is drawn.
+    pub fn sprites_mut(&mut self, sprites: &SpriteRange) -> (&mut [Transform], &mut [SheetRegion]) {
+        let range = sprites.range();
+        self.slots
+            .borrow_mut()
+            .mark_dirty(sprites.group().0, range.clone());
+        let (trfs, uvs) = self.get_sprites_mut(sprites.group());
+        (&mut trfs[range.clone()], &mut uvs[range])
+    }
+
+    pub fn sprite_mut(&mut self, sprite: &Sprite) -> (&mut Transform, &mut SheetRegion) {
+        let (trfs, uvs) = self.sprites_mut(&sprite.0);
+        (&mut trfs[0], &mut uvs[0])
+    }
+
+    /// Write `text` in `font` into the sprites behind a handle, starting at `pos`.
+    /// Characters past the end of the range are dropped and leftover slots are
+    /// blanked. Returns how many characters were drawn; headless renderers can't
+    /// lay out glyphs, so they only blank the range.
+    pub fn draw_text(
+        &mut self,
+        font: &BitFont,
+        sprites: &SpriteRange,
+        text: &str,
+        pos: Vec2,
+        char_height: f32,
+    ) -> usize {
+        let range = sprites.range();
+        let text: String = text.chars().take(range.len()).collect();
+        let (trfs, _uvs) = self.sprites_mut(sprites);
+        trfs.fill(Transform::zeroed());
+        match &mut self.backend {
+            Backend::Gpu(frend) => {
+                let (_end, drawn) = font.draw_text(
+                    &mut frend.sprites,
+                    sprites.group().0,
+                    range.start,
+                    &text,
+                    pos.into(),
+                    char_height,
+                );
+                drawn
+            }
+            Backend::Headless { .. } => 0,
+        }
+    }
+
+    fn clear_released(&mut self) {
+        let released = self.slots.borrow_mut().take_released();
+        for (group, ranges) in released {
+            let group = SpriteGroup(group);
+            for range in ranges {
+                let (trfs, _uvs) = self.get_sprites_mut(group);
+                trfs[range.clone()].fill(Transform::zeroed());
+                self.slots.borrow_mut().mark_dirty(group.0, range);
+            }
+        }
+    }
+
+    /// Upload every range written through a handle since the last frame.
+    pub(crate) fn upload_dirty(&mut self) {
+        self.clear_released();
+        let dirty = self.slots.borrow_mut().take_dirty();
+        for (group, ranges) in dirty {
+            for range in ranges {
+                self.upload_sprites(SpriteGroup(group), range);
+            }
+        }
+    }
+
+    /// Point every sprite group at `camera`, letterboxed to fit the surface.
+    pub fn set_camera_all(&mut self, camera: Camera) {
+        self.camera = camera;
+        let fitted = self.fitted_camera();
+        if let Backend::Gpu(frend) = &mut self.backend {
+            frend.sprites.set_camera_all(&frend.gpu, fitted);
+        }
+    }
+
+    /// The camera most recently set by the game.
+    pub fn camera(&self) -> Camera {
+        self.camera
+    }
+
+    fn fitted_camera(&self) -> Camera {
+        let (w, h) = self.surface_size();
+        letterbox(self.camera, Vec2::new(w as f32, h as f32))
+    }
+
+    /// The sprites most recently uploaded to a group, i.e. what would be on screen.
+    /// Only available on headless renderers.
+    pub fn uploaded_sprites(&self, which: SpriteGroup) -> (&[Transform], &[SheetRegion]) {
+        match &self.backend {
+            Backend::Gpu(_) => panic!("uploaded_sprites is only recorded by headless renderers"),
+            Backend::Headless { groups, .. } => {
+                let group = &groups[which.0];
+                (&group.uploaded_trfs, &group.uploaded_uvs)
+            }
+        }
+    }
+
+    /// How many frames have been presented so far (headless renderers only count).
+    pub fn frames_rendered(&self) -> usize {
+        match &self.backend {
+            Backend::Gpu(_) => 0,
+            Backend::Headless { frames, .. } => *frames,
+        }
+    }
+
+    pub(crate) fn render(&mut self) {
+        self.upload_dirty();
+        match &mut self.backend {
+            Backend::Gpu(frend) => frend.render(),
+            Backend::Headless { frames, .. } => *frames += 1,
+        }
+    }
+
+    pub(crate) fn process_window_event(&mut self, event: &winit::event::Event<()>) -> bool {
+        let changed = match &mut self.backend {
+            Backend::Gpu(frend) => frend.process_window_event(event),
+            Backend::Headless { .. } => false,
+        };
+        if changed {
+            // the surface may have been resized, so refit the camera to it
+            self.set_camera_all(self.camera);
+        }
+        changed
+    }
+}
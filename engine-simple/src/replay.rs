@@ -0,0 +1,343 @@
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{
+    DeviceId, ElementState, Event, KeyboardInput, ModifiersState, MouseButton, WindowEvent,
+};
+use winit::window::WindowId;
+
+use crate::geom::{screen_to_world, world_to_screen, Vec2};
+use crate::{Camera, Key};
+
+/// A single input change, as seen by `Input`.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum InputEvent {
+    Key {
+        key: Key,
+        pressed: bool,
+    },
+    Mouse {
+        button: MouseButton,
+        pressed: bool,
+    },
+    /// In physical window pixels, from the top left corner.
+    MouseMove {
+        x: f64,
+        y: f64,
+    },
+    /// The window's new physical size, which later mouse positions are within.
+    Resize {
+        width: u32,
+        height: u32,
+    },
+}
+
+impl InputEvent {
+    /// Pull the parts of a winit event that `Input` cares about, if any.
+    pub fn from_winit<T>(event: &Event<T>) -> Option<Self> {
+        let Event::WindowEvent { event, .. } = event else {
+            return None;
+        };
+        match event {
+            WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state,
+                        virtual_keycode: Some(key),
+                        ..
+                    },
+                ..
+            } => Some(InputEvent::Key {
+                key: *key,
+                pressed: *state == ElementState::Pressed,
+            }),
+            WindowEvent::MouseInput { state, button, .. } => Some(InputEvent::Mouse {
+                button: *button,
+                pressed: *state == ElementState::Pressed,
+            }),
+            WindowEvent::CursorMoved { position, .. } => Some(InputEvent::MouseMove {
+                x: position.x,
+                y: position.y,
+            }),
+            WindowEvent::Resized(size) => Some(InputEvent::Resize {
+                width: size.width,
+                height: size.height,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Rebuild an equivalent winit event so it can be fed back through `Input`.
+    #[allow(deprecated)]
+    pub fn to_winit(self) -> Event<'static, ()> {
+        // synthetic events have no real window or device behind them
+        let device_id = unsafe { DeviceId::dummy() };
+        let state = |pressed| {
+            if pressed {
+                ElementState::Pressed
+            } else {
+                ElementState::Released
+            }
+        };
+        let event = match self {
+            InputEvent::Key { key, pressed } => WindowEvent::KeyboardInput {
+                device_id,
+                input: KeyboardInput {
+                    scancode: 0,
+                    state: state(pressed),
+                    virtual_keycode: Some(key),
+                    modifiers: ModifiersState::empty(),
+                },
+                is_synthetic: true,
+            },
+            InputEvent::Mouse { button, pressed } => WindowEvent::MouseInput {
+                device_id,
+                state: state(pressed),
+                button,
+                modifiers: ModifiersState::empty(),
+            },
+            InputEvent::MouseMove { x, y } => WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(x, y),
+                modifiers: ModifiersState::empty(),
+            },
+            InputEvent::Resize { width, height } => {
+                WindowEvent::Resized(PhysicalSize::new(width, height))
+            }
+        };
+        Event::WindowEvent {
+            window_id: unsafe { WindowId::dummy() },
+            event,
+        }
+    }
+}
+
+/// Everything needed to replay a session: the RNG seed, the window size it
+/// started at and the input that arrived before each fixed step.
+///
+/// Mouse positions are kept in the window pixels they were recorded in; a
+/// replay maps them through the camera into whatever window it runs in.
+///
+/// On disk it's a line of JSON for the seed and window size, then one per step.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub window_size: (u32, u32),
+    pub frames: Vec<Vec<InputEvent>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    seed: u64,
+    window_size: (u32, u32),
+}
+
+impl Recording {
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let text = std::fs::read_to_string(path)?;
+        let lines: Vec<&str> = text.lines().collect();
+        let header: Header = serde_json::from_str(lines.first().copied().unwrap_or_default())?;
+        let mut frames = Vec::default();
+        for (i, line) in lines.iter().enumerate().skip(1) {
+            match serde_json::from_str(line) {
+                Ok(frame) => frames.push(frame),
+                // a crash in the middle of writing can cut the last line short
+                Err(_) if i == lines.len() - 1 => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(Self {
+            seed: header.seed,
+            window_size: header.window_size,
+            frames,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        write_line(&mut file, &self.header())?;
+        for frame in self.frames.iter() {
+            write_line(&mut file, frame)?;
+        }
+        file.flush()
+    }
+
+    fn header(&self) -> Header {
+        Header {
+            seed: self.seed,
+            window_size: self.window_size,
+        }
+    }
+}
+
+fn write_line(out: &mut impl Write, value: &impl Serialize) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    out.write_all(b"\n")
+}
+
+/// Writes a [`Recording`] out step by step. Writes are buffered and only
+/// flushed on [`Recorder::flush`], or when the recorder is dropped.
+pub(crate) struct Recorder {
+    file: std::io::BufWriter<std::fs::File>,
+    pending: Vec<InputEvent>,
+}
+
+impl Recorder {
+    pub(crate) fn new(path: &Path, seed: u64, window_size: (u32, u32)) -> std::io::Result<Self> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        write_line(&mut file, &Header { seed, window_size })?;
+        Ok(Self {
+            file,
+            pending: Vec::default(),
+        })
+    }
+
+    pub(crate) fn record(&mut self, event: InputEvent) {
+        self.pending.push(event);
+    }
+
+    pub(crate) fn end_frame(&mut self) -> std::io::Result<()> {
+        let frame = std::mem::take(&mut self.pending);
+        write_line(&mut self.file, &frame)
+    }
+
+    pub(crate) fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            eprintln!("couldn't save input recording: {err}");
+        }
+    }
+}
+
+pub(crate) struct Playback {
+    recording: Recording,
+    frame: usize,
+    // the window size the upcoming mouse positions were recorded in
+    window_size: Vec2,
+}
+
+impl Playback {
+    pub(crate) fn new(recording: Recording) -> Self {
+        let (w, h) = recording.window_size;
+        Self {
+            recording,
+            frame: 0,
+            window_size: Vec2::new(w as f32, h as f32),
+        }
+    }
+
+    /// Input for the next fixed step, or `None` once the recording runs out.
+    /// Mouse positions are moved so they point at the same spot in the world
+    /// through `camera` in a window of `surface_size`.
+    pub(crate) fn next_frame(
+        &mut self,
+        camera: Camera,
+        surface_size: Vec2,
+    ) -> Option<Vec<InputEvent>> {
+        let events = self.recording.frames.get(self.frame)?;
+        self.frame += 1;
+        let mut live = Vec::with_capacity(events.len());
+        for event in events.iter() {
+            match *event {
+                InputEvent::Resize { width, height } => {
+                    self.window_size = Vec2::new(width as f32, height as f32);
+                }
+                InputEvent::MouseMove { x, y } => {
+                    let pos = Vec2::new(x as f32, y as f32);
+                    let world = screen_to_world(camera, self.window_size, pos);
+                    let pos = world_to_screen(camera, surface_size, world);
+                    live.push(InputEvent::MouseMove {
+                        x: pos.x as f64,
+                        y: pos.y as f64,
+                    });
+                }
+                event => live.push(event),
+            }
+        }
+        Some(live)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Zeroable;
+
+    #[test]
+    fn recordings_survive_a_round_trip_through_a_file() {
+        let path = std::env::temp_dir().join("engine-simple-recording-test.json");
+        let mut recorder = Recorder::new(&path, 42, (640, 480)).unwrap();
+        recorder.record(InputEvent::Key {
+            key: Key::Left,
+            pressed: true,
+        });
+        recorder.end_frame().unwrap();
+        recorder.record(InputEvent::MouseMove { x: 10.0, y: 20.0 });
+        recorder.record(InputEvent::Resize {
+            width: 800,
+            height: 600,
+        });
+        recorder.end_frame().unwrap();
+        recorder.end_frame().unwrap();
+        // dropping it flushes what's buffered
+        drop(recorder);
+
+        let recording = Recording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!((recording.seed, recording.window_size), (42, (640, 480)));
+        assert_eq!(recording.frames.len(), 3);
+        assert_eq!(
+            recording.frames[1],
+            [
+                InputEvent::MouseMove { x: 10.0, y: 20.0 },
+                InputEvent::Resize {
+                    width: 800,
+                    height: 600
+                }
+            ]
+        );
+        assert!(recording.frames[2].is_empty());
+    }
+
+    #[test]
+    fn replayed_mouse_positions_follow_the_window_size() {
+        let camera = Camera {
+            screen_pos: [0.0, 0.0],
+            screen_size: [320.0, 240.0],
+        };
+        let recording = Recording {
+            seed: 0,
+            window_size: (320, 240),
+            frames: vec![
+                vec![InputEvent::MouseMove { x: 160.0, y: 60.0 }],
+                vec![
+                    InputEvent::Resize {
+                        width: 1280,
+                        height: 720,
+                    },
+                    InputEvent::MouseMove { x: 640.0, y: 180.0 },
+                ],
+            ],
+        };
+        let mut playback = Playback::new(recording);
+        // replayed in a window twice the size
+        let surface = Vec2::new(640.0, 480.0);
+        assert_eq!(
+            playback.next_frame(camera, surface).unwrap(),
+            [InputEvent::MouseMove { x: 320.0, y: 120.0 }]
+        );
+        // the recorded window had been widened, so the picture was letterboxed
+        assert_eq!(
+            playback.next_frame(camera, surface).unwrap(),
+            [InputEvent::MouseMove { x: 320.0, y: 120.0 }]
+        );
+        assert_eq!(playback.next_frame(Camera::zeroed(), surface), None);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::{Engine, SpriteGroup, Transform, Zeroable};
+
+/// What the top scene wants to happen to the stack after an update.
+pub enum Transition<S> {
+    None,
+    /// Put a new scene on top of this one.
+    Push(Box<dyn Scene<S>>),
+    /// Remove this scene, returning to the one underneath.
+    Pop,
+    /// Swap this scene out for another.
+    Replace(Box<dyn Scene<S>>),
+}
+
+/// One screen or mode of a game. `S` is the state shared by every scene.
+pub trait Scene<S> {
+    fn on_enter(&mut self, _state: &mut S, _engine: &mut Engine) {}
+    fn on_exit(&mut self, _state: &mut S, _engine: &mut Engine) {}
+    /// Only called on the scene at the top of the stack.
+    fn update(&mut self, state: &mut S, engine: &mut Engine) -> Transition<S>;
+    /// Called on every visible scene, bottom to top, each frame. A scene should
+    /// rewrite all of its sprites here: when a transition hides another scene
+    /// that shares one of its groups, that whole group is blanked first.
+    fn render(&mut self, state: &mut S, engine: &mut Engine);
+    /// Sprite groups this scene draws into; they are blanked when it stops being visible.
+    fn sprite_groups(&self) -> &[SpriteGroup] {
+        &[]
+    }
+    /// Overlays let the scene underneath keep drawing (but not updating).
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+/// A stack of scenes where only the top one updates.
+pub struct SceneStack<S> {
+    scenes: Vec<Box<dyn Scene<S>>>,
+}
+
+impl<S> Default for SceneStack<S> {
+    fn default() -> Self {
+        Self {
+            scenes: Vec::default(),
+        }
+    }
+}
+
+impl<S> SceneStack<S> {
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+
+    pub fn push(&mut self, mut scene: Box<dyn Scene<S>>, state: &mut S, engine: &mut Engine) {
+        // an overlay leaves everything under it on screen
+        let covered = if scene.is_overlay() {
+            self.scenes.len()
+        } else {
+            self.first_visible()
+        };
+        hide_groups(self.groups_from(covered), engine);
+        scene.on_enter(state, engine);
+        self.scenes.push(scene);
+    }
+
+    pub fn pop(&mut self, state: &mut S, engine: &mut Engine) -> Option<Box<dyn Scene<S>>> {
+        let mut scene = self.scenes.pop()?;
+        scene.on_exit(state, engine);
+        hide_groups(scene.sprite_groups().to_vec(), engine);
+        Some(scene)
+    }
+
+    pub fn replace(&mut self, mut scene: Box<dyn Scene<S>>, state: &mut S, engine: &mut Engine) {
+        let covered = if scene.is_overlay() {
+            self.scenes.len().saturating_sub(1)
+        } else {
+            self.first_visible()
+        };
+        let hidden = self.groups_from(covered);
+        if let Some(mut old) = self.scenes.pop() {
+            old.on_exit(state, engine);
+        }
+        hide_groups(hidden, engine);
+        scene.on_enter(state, engine);
+        self.scenes.push(scene);
+    }
+
+    /// Update the top scene and apply whatever transition it asks for.
+    pub fn update(&mut self, state: &mut S, engine: &mut Engine) {
+        let Some(top) = self.scenes.last_mut() else {
+            return;
+        };
+        match top.update(state, engine) {
+            Transition::None => (),
+            Transition::Push(scene) => self.push(scene, state, engine),
+            Transition::Pop => {
+                self.pop(state, engine);
+            }
+            Transition::Replace(scene) => self.replace(scene, state, engine),
+        }
+    }
+
+    pub fn render(&mut self, state: &mut S, engine: &mut Engine) {
+        let first = self.first_visible();
+        for scene in self.scenes[first..].iter_mut() {
+            scene.render(state, engine);
+        }
+    }
+
+    // index of the lowest scene that can still be seen through the overlays above it
+    fn first_visible(&self) -> usize {
+        let mut first = self.scenes.len();
+        while first > 0 {
+            first -= 1;
+            if !self.scenes[first].is_overlay() {
+                break;
+            }
+        }
+        first
+    }
+
+    // groups drawn by the scenes from `first` up
+    fn groups_from(&self, first: usize) -> Vec<SpriteGroup> {
+        self.scenes[first..]
+            .iter()
+            .flat_map(|scene| scene.sprite_groups().iter().copied())
+            .collect()
+    }
+}
+
+// blank the groups of scenes a transition hides, so they don't leave sprites
+// behind. This happens before the incoming scene's on_enter so anything it draws
+// there stays; visible scenes sharing one of these groups redraw it in render.
+fn hide_groups(hidden: Vec<SpriteGroup>, engine: &mut Engine) {
+    for group in hidden {
+        let (trfs, _uvs) = engine.renderer.get_sprites_mut(group);
+        trfs.fill(Transform::zeroed());
+        let len = trfs.len();
+        engine.renderer.upload_sprites(group, 0..len);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Camera, Sprite};
+
+    // Draws one sprite in its group as soon as it's entered
+    struct Marker {
+        group: SpriteGroup,
+        x: f32,
+        overlay: bool,
+        sprite: Option<Sprite>,
+    }
+
+    impl Scene<()> for Marker {
+        fn on_enter(&mut self, _state: &mut (), engine: &mut Engine) {
+            let sprite = engine.renderer.alloc_sprite(self.group);
+            let (trf, _uv) = engine.renderer.sprite_mut(&sprite);
+            trf.x = self.x;
+            trf.w = 8;
+            self.sprite = Some(sprite);
+        }
+        fn update(&mut self, _state: &mut (), _engine: &mut Engine) -> Transition<()> {
+            Transition::None
+        }
+        fn render(&mut self, _state: &mut (), _engine: &mut Engine) {}
+        fn sprite_groups(&self) -> &[SpriteGroup] {
+            std::slice::from_ref(&self.group)
+        }
+        fn is_overlay(&self) -> bool {
+            self.overlay
+        }
+    }
+
+    fn marker(group: SpriteGroup, x: f32, overlay: bool) -> Box<dyn Scene<()>> {
+        Box::new(Marker {
+            group,
+            x,
+            overlay,
+            sprite: None,
+        })
+    }
+
+    fn new_group(engine: &mut Engine) -> SpriteGroup {
+        let tex = engine
+            .renderer
+            .create_texture(&image::RgbaImage::new(1, 1), "test");
+        engine
+            .renderer
+            .add_sprite_group(&tex, Vec::default(), Vec::default(), Camera::zeroed())
+    }
+
+    fn drawn(engine: &Engine, group: SpriteGroup) -> Vec<f32> {
+        let (trfs, _uvs) = engine.renderer.uploaded_sprites(group);
+        trfs.iter()
+            .filter(|trf| trf.w > 0)
+            .map(|trf| trf.x)
+            .collect()
+    }
+
+    #[test]
+    fn entering_a_scene_keeps_what_it_drew_in_a_shared_group() {
+        let mut engine = Engine::headless(320, 240);
+        let group = new_group(&mut engine);
+        let mut stack = SceneStack::default();
+        stack.push(marker(group, 1.0, false), &mut (), &mut engine);
+        stack.push(marker(group, 2.0, false), &mut (), &mut engine);
+        engine.renderer.render();
+        // the covered scene is blanked, the new one's sprite survives
+        assert_eq!(drawn(&engine, group), vec![2.0]);
+
+        stack.replace(marker(group, 3.0, false), &mut (), &mut engine);
+        engine.renderer.render();
+        assert_eq!(drawn(&engine, group), vec![3.0]);
+    }
+
+    #[test]
+    fn only_scenes_that_become_hidden_are_blanked() {
+        let mut engine = Engine::headless(320, 240);
+        let (below, above) = (new_group(&mut engine), new_group(&mut engine));
+        let mut stack = SceneStack::default();
+        stack.push(marker(below, 1.0, false), &mut (), &mut engine);
+        stack.push(marker(above, 2.0, true), &mut (), &mut engine);
+        engine.renderer.render();
+        // the scene under the overlay is still on screen
+        assert_eq!(drawn(&engine, below), vec![1.0]);
+        assert_eq!(drawn(&engine, above), vec![2.0]);
+
+        stack.replace(marker(above, 3.0, true), &mut (), &mut engine);
+        engine.renderer.render();
+        assert_eq!(drawn(&engine, below), vec![1.0]);
+        assert_eq!(drawn(&engine, above), vec![3.0]);
+
+        stack.pop(&mut (), &mut engine);
+        engine.renderer.render();
+        assert_eq!(drawn(&engine, below), vec![1.0]);
+        assert!(drawn(&engine, above).is_empty());
+
+        stack.push(marker(above, 4.0, false), &mut (), &mut engine);
+        engine.renderer.render();
+        assert!(drawn(&engine, below).is_empty());
+    }
+}
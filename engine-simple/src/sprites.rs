@@ -0,0 +1,192 @@
+use std::cell::RefCell;
+use std::ops::Range;
+use std::rc::Rc;
+
+// Bookkeeping for the slots of one sprite group.
+#[derive(Default)]
+struct GroupSlots {
+    // free ranges below `end`, sorted and merged
+    free: Vec<Range<usize>>,
+    // everything at or past `end` is unused
+    end: usize,
+    dirty: Vec<Range<usize>>,
+    // freed by dropped handles but not yet blanked
+    released: Vec<Range<usize>>,
+}
+
+impl GroupSlots {
+    fn alloc(&mut self, len: usize) -> usize {
+        if let Some(i) = self.free.iter().position(|r| r.len() >= len) {
+            let start = self.free[i].start;
+            self.free[i].start += len;
+            if self.free[i].is_empty() {
+                self.free.remove(i);
+            }
+            return start;
+        }
+        let start = self.end;
+        self.end += len;
+        start
+    }
+
+    fn release(&mut self, range: Range<usize>) {
+        self.released.push(range.clone());
+        self.free.push(range);
+        self.free.sort_by_key(|r| r.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(self.free.len());
+        for r in self.free.drain(..) {
+            match merged.last_mut() {
+                Some(last) if last.end >= r.start => last.end = last.end.max(r.end),
+                _ => merged.push(r),
+            }
+        }
+        // give trailing free space back to the end of the group
+        if merged.last().is_some_and(|last| last.end == self.end) {
+            self.end = merged.pop().unwrap().start;
+        }
+        self.free = merged;
+    }
+}
+
+/// Tracks which slots of each sprite group are handed out and which need uploading.
+#[derive(Default)]
+pub(crate) struct SpriteSlots {
+    groups: Vec<GroupSlots>,
+}
+
+impl SpriteSlots {
+    pub(crate) fn add_group(&mut self) {
+        self.groups.push(GroupSlots::default());
+    }
+
+    pub(crate) fn alloc(&mut self, group: usize, len: usize) -> Range<usize> {
+        let start = self.groups[group].alloc(len);
+        start..start + len
+    }
+
+    /// One past the last slot in use, i.e. how big the group needs to be.
+    pub(crate) fn end(&self, group: usize) -> usize {
+        self.groups[group].end
+    }
+
+    pub(crate) fn mark_dirty(&mut self, group: usize, range: Range<usize>) {
+        self.groups[group].dirty.push(range);
+    }
+
+    /// Slots freed since the last call, per group.
+    pub(crate) fn take_released(&mut self) -> Vec<(usize, Vec<Range<usize>>)> {
+        self.groups
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, slots)| !slots.released.is_empty())
+            .map(|(group, slots)| (group, std::mem::take(&mut slots.released)))
+            .collect()
+    }
+
+    /// Dirty ranges per group, sorted and merged so each is uploaded once.
+    pub(crate) fn take_dirty(&mut self) -> Vec<(usize, Vec<Range<usize>>)> {
+        let mut out = Vec::default();
+        for (group, slots) in self.groups.iter_mut().enumerate() {
+            if slots.dirty.is_empty() {
+                continue;
+            }
+            let mut dirty = std::mem::take(&mut slots.dirty);
+            dirty.sort_by_key(|r| r.start);
+            let mut merged: Vec<Range<usize>> = Vec::with_capacity(dirty.len());
+            for r in dirty {
+                match merged.last_mut() {
+                    Some(last) if last.end >= r.start => last.end = last.end.max(r.end),
+                    _ => merged.push(r),
+                }
+            }
+            out.push((group, merged));
+        }
+        out
+    }
+}
+
+/// A sprite group, as made by [`crate::Renderer::add_sprite_group`].
+/// Hand it to [`crate::Renderer::alloc_sprites`] to get slots in it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct SpriteGroup(pub(crate) usize);
+
+impl SpriteGroup {
+    /// Where it is among the renderer's groups, which is also the `sheet` its
+    /// sprites' [`crate::SheetRegion`]s use.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+/// A run of sprite slots in one group, freed when dropped.
+/// Get at the sprites with [`crate::Renderer::sprites_mut`].
+pub struct SpriteRange {
+    group: SpriteGroup,
+    start: usize,
+    len: usize,
+    slots: Rc<RefCell<SpriteSlots>>,
+}
+
+impl SpriteRange {
+    pub(crate) fn new(
+        group: SpriteGroup,
+        range: Range<usize>,
+        slots: Rc<RefCell<SpriteSlots>>,
+    ) -> Self {
+        Self {
+            group,
+            start: range.start,
+            len: range.len(),
+            slots,
+        }
+    }
+
+    pub fn group(&self) -> SpriteGroup {
+        self.group
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The slots this handle owns within its group.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.start + self.len
+    }
+}
+
+impl Drop for SpriteRange {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            self.slots.borrow_mut().groups[self.group.0].release(self.range());
+        }
+    }
+}
+
+impl std::fmt::Debug for SpriteRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SpriteRange")
+            .field("group", &self.group)
+            .field("range", &self.range())
+            .finish()
+    }
+}
+
+/// A single sprite slot, freed when dropped.
+/// Get at the sprite with [`crate::Renderer::sprite_mut`].
+#[derive(Debug)]
+pub struct Sprite(pub(crate) SpriteRange);
+
+impl Sprite {
+    pub fn group(&self) -> SpriteGroup {
+        self.0.group
+    }
+
+    pub fn index(&self) -> usize {
+        self.0.start
+    }
+}
@@ -0,0 +1,376 @@
+use std::time::Duration;
+
+use crate::{Engine, DT};
+
+/// A countdown measured in simulation time. Call [`Timer::tick`] once per fixed update.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Timer {
+    duration: f32,
+    remaining: f32,
+    repeating: bool,
+    finished: bool,
+}
+
+impl Timer {
+    /// Fires once, `delay` after it was created or last reset.
+    pub fn after(delay: Duration) -> Self {
+        Self::new(delay, false)
+    }
+
+    /// Fires every `interval`, starting one interval from now.
+    pub fn every(interval: Duration) -> Self {
+        Self::new(interval, true)
+    }
+
+    fn new(duration: Duration, repeating: bool) -> Self {
+        let duration = duration.as_secs_f32();
+        Self {
+            duration,
+            remaining: duration,
+            repeating,
+            finished: false,
+        }
+    }
+
+    /// Advance by one fixed step, returning true if the timer fired.
+    pub fn tick(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+        self.remaining -= DT;
+        // a little slack so a 0.5s timer fires on step 30 rather than 31
+        if self.remaining > 1e-4 {
+            return false;
+        }
+        if self.repeating {
+            self.remaining += self.duration;
+        } else {
+            self.finished = true;
+        }
+        true
+    }
+
+    /// Start counting down from the full duration again.
+    pub fn reset(&mut self) {
+        self.remaining = self.duration;
+        self.finished = false;
+    }
+
+    /// True once a one-shot timer has fired.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// How far through the current countdown we are, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.finished || self.duration <= 0.0 {
+            1.0
+        } else {
+            1.0 - self.remaining.max(0.0) / self.duration
+        }
+    }
+}
+
+/// Identifies a scheduled task (or every step of a sequence) so it can be cancelled.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TaskId(u64);
+
+/// Callbacks get the scheduler they run on, so they can schedule follow-ups.
+pub type Callback<S> = Box<dyn FnMut(&mut S, &mut Scheduler<S>, &mut Engine)>;
+
+struct Task<S> {
+    id: TaskId,
+    due: f64,
+    interval: Option<f64>,
+    callback: Callback<S>,
+}
+
+/// Runs callbacks at points in simulation time. `S` is whatever state the
+/// callbacks need besides the engine.
+pub struct Scheduler<S> {
+    tasks: Vec<Task<S>>,
+    next_id: u64,
+    // the task whose callback is running, until something cancels it
+    running: Option<TaskId>,
+}
+
+impl<S> Default for Scheduler<S> {
+    fn default() -> Self {
+        Self {
+            tasks: Vec::default(),
+            next_id: 0,
+            running: None,
+        }
+    }
+}
+
+impl<S> Scheduler<S> {
+    /// Run `callback` once, `delay` from now.
+    pub fn after(
+        &mut self,
+        engine: &Engine,
+        delay: Duration,
+        callback: impl FnMut(&mut S, &mut Scheduler<S>, &mut Engine) + 'static,
+    ) -> TaskId {
+        let id = self.new_id();
+        self.tasks.push(Task {
+            id,
+            due: engine.time() + delay.as_secs_f64(),
+            interval: None,
+            callback: Box::new(callback),
+        });
+        id
+    }
+
+    /// Run `callback` every `interval` until cancelled, starting one interval
+    /// from now. It runs at most once per update however short `interval` is.
+    pub fn every(
+        &mut self,
+        engine: &Engine,
+        interval: Duration,
+        callback: impl FnMut(&mut S, &mut Scheduler<S>, &mut Engine) + 'static,
+    ) -> TaskId {
+        let id = self.new_id();
+        let interval = interval.as_secs_f64().max(DT as f64);
+        self.tasks.push(Task {
+            id,
+            due: engine.time() + interval,
+            interval: Some(interval),
+            callback: Box::new(callback),
+        });
+        id
+    }
+
+    /// Run each step after waiting its delay, one after another. Cancelling the
+    /// returned id drops whichever steps haven't run yet.
+    pub fn sequence(
+        &mut self,
+        engine: &Engine,
+        steps: impl IntoIterator<Item = (Duration, Callback<S>)>,
+    ) -> TaskId {
+        let id = self.new_id();
+        let mut due = engine.time();
+        for (delay, callback) in steps {
+            due += delay.as_secs_f64();
+            self.tasks.push(Task {
+                id,
+                due,
+                interval: None,
+                callback,
+            });
+        }
+        id
+    }
+
+    /// Drop a task, or every remaining step of a sequence. Callbacks can
+    /// cancel their own task to stop it repeating.
+    pub fn cancel(&mut self, id: TaskId) {
+        self.tasks.retain(|task| task.id != id);
+        if self.running == Some(id) {
+            self.running = None;
+        }
+    }
+
+    /// The task whose callback is running right now, so it can cancel itself.
+    pub fn running(&self) -> Option<TaskId> {
+        self.running
+    }
+
+    pub fn is_scheduled(&self, id: TaskId) -> bool {
+        self.tasks.iter().any(|task| task.id == id)
+    }
+
+    /// Run everything that has come due, including anything the callbacks
+    /// schedule that is already due. Call once per fixed update.
+    pub fn update(&mut self, state: &mut S, engine: &mut Engine) {
+        let now = engine.time();
+        // run in due order so sequence steps that land on the same update stay in order
+        while let Some(next) = self
+            .tasks
+            .iter()
+            .enumerate()
+            .filter(|(_, task)| task.due <= now + 1e-6)
+            .min_by(|(_, a), (_, b)| a.due.total_cmp(&b.due))
+            .map(|(i, _)| i)
+        {
+            let mut task = self.tasks.remove(next);
+            self.running = Some(task.id);
+            (task.callback)(state, self, engine);
+            // a repeating task keeps going unless its callback cancelled it
+            if let (Some(interval), Some(_)) = (task.interval, self.running.take()) {
+                task.due += interval;
+                self.tasks.push(task);
+            }
+        }
+    }
+
+    fn new_id(&mut self) -> TaskId {
+        self.next_id += 1;
+        TaskId(self.next_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Advance `steps` fixed updates, running the scheduler after each one
+    fn run(
+        scheduler: &mut Scheduler<Vec<u32>>,
+        log: &mut Vec<u32>,
+        engine: &mut Engine,
+        steps: usize,
+    ) {
+        for _ in 0..steps {
+            engine.time += DT as f64;
+            scheduler.update(log, engine);
+        }
+    }
+
+    fn step(n: u32) -> (Duration, Callback<Vec<u32>>) {
+        (
+            Duration::from_millis(100),
+            Box::new(
+                move |log: &mut Vec<u32>, _: &mut Scheduler<Vec<u32>>, _: &mut Engine| log.push(n),
+            ),
+        )
+    }
+
+    #[test]
+    fn timer_fires_on_the_right_step() {
+        let mut timer = Timer::after(Duration::from_millis(500));
+        let fired: Vec<bool> = (0..31).map(|_| timer.tick()).collect();
+        assert_eq!(fired.iter().position(|&fired| fired), Some(29));
+        assert!(timer.is_finished());
+        assert_eq!(fired.iter().filter(|&&fired| fired).count(), 1);
+
+        let mut timer = Timer::every(Duration::from_millis(100));
+        let fired = (0..60).filter(|_| timer.tick()).count();
+        assert_eq!(fired, 10);
+    }
+
+    #[test]
+    fn after_runs_once_when_due() {
+        let mut engine = Engine::headless(1, 1);
+        let mut scheduler = Scheduler::default();
+        let mut log = Vec::default();
+        scheduler.after(
+            &engine,
+            Duration::from_millis(500),
+            |log: &mut Vec<u32>, _, _| log.push(1),
+        );
+        run(&mut scheduler, &mut log, &mut engine, 29);
+        assert!(log.is_empty());
+        run(&mut scheduler, &mut log, &mut engine, 1);
+        assert_eq!(log, [1]);
+        run(&mut scheduler, &mut log, &mut engine, 60);
+        assert_eq!(log, [1]);
+    }
+
+    #[test]
+    fn every_repeats_until_cancelled() {
+        let mut engine = Engine::headless(1, 1);
+        let mut scheduler = Scheduler::default();
+        let mut log = Vec::default();
+        let id = scheduler.every(
+            &engine,
+            Duration::from_millis(250),
+            |log: &mut Vec<u32>, _, _| log.push(1),
+        );
+        run(&mut scheduler, &mut log, &mut engine, 60);
+        assert_eq!(log.len(), 4);
+        assert!(scheduler.is_scheduled(id));
+        scheduler.cancel(id);
+        assert!(!scheduler.is_scheduled(id));
+        run(&mut scheduler, &mut log, &mut engine, 60);
+        assert_eq!(log.len(), 4);
+    }
+
+    #[test]
+    fn sequence_runs_steps_in_order() {
+        let mut engine = Engine::headless(1, 1);
+        let mut scheduler = Scheduler::default();
+        let mut log = Vec::default();
+        scheduler.sequence(&engine, [step(1), step(2), step(3)]);
+        run(&mut scheduler, &mut log, &mut engine, 6);
+        assert_eq!(log, [1]);
+        run(&mut scheduler, &mut log, &mut engine, 6);
+        assert_eq!(log, [1, 2]);
+        run(&mut scheduler, &mut log, &mut engine, 6);
+        assert_eq!(log, [1, 2, 3]);
+
+        // steps that come due on the same update still run in order
+        log.clear();
+        let at_once = [1, 2, 3].map(|n| {
+            let (_, callback) = step(n);
+            (Duration::ZERO, callback)
+        });
+        scheduler.sequence(&engine, at_once);
+        run(&mut scheduler, &mut log, &mut engine, 1);
+        assert_eq!(log, [1, 2, 3]);
+    }
+
+    #[test]
+    fn cancelling_a_sequence_drops_the_rest() {
+        let mut engine = Engine::headless(1, 1);
+        let mut scheduler = Scheduler::default();
+        let mut log = Vec::default();
+        let id = scheduler.sequence(&engine, [step(1), step(2), step(3)]);
+        let other = scheduler.after(
+            &engine,
+            Duration::from_millis(300),
+            |log: &mut Vec<u32>, _, _| log.push(9),
+        );
+        run(&mut scheduler, &mut log, &mut engine, 6);
+        scheduler.cancel(id);
+        assert!(!scheduler.is_scheduled(id));
+        run(&mut scheduler, &mut log, &mut engine, 60);
+        assert_eq!(log, [1, 9]);
+        assert!(!scheduler.is_scheduled(other));
+    }
+
+    #[test]
+    fn callbacks_can_schedule_follow_ups_and_cancel_themselves() {
+        let mut engine = Engine::headless(1, 1);
+        let mut scheduler = Scheduler::default();
+        let mut log = Vec::default();
+        scheduler.after(
+            &engine,
+            Duration::from_millis(100),
+            |log: &mut Vec<u32>, scheduler, engine| {
+                log.push(1);
+                scheduler.after(
+                    engine,
+                    Duration::from_millis(100),
+                    |log: &mut Vec<u32>, _, _| log.push(2),
+                );
+                // already due, so it runs on this same update
+                scheduler.after(engine, Duration::ZERO, |log: &mut Vec<u32>, _, _| {
+                    log.push(3)
+                });
+            },
+        );
+        run(&mut scheduler, &mut log, &mut engine, 6);
+        assert_eq!(log, [1, 3]);
+        run(&mut scheduler, &mut log, &mut engine, 6);
+        assert_eq!(log, [1, 3, 2]);
+
+        log.clear();
+        let mut count = 0;
+        let id = scheduler.every(
+            &engine,
+            Duration::from_millis(100),
+            move |log: &mut Vec<u32>, scheduler, _| {
+                count += 1;
+                log.push(count);
+                if count == 2 {
+                    let id = scheduler.running.unwrap();
+                    scheduler.cancel(id);
+                }
+            },
+        );
+        run(&mut scheduler, &mut log, &mut engine, 60);
+        assert_eq!(log, [1, 2]);
+        assert!(!scheduler.is_scheduled(id));
+    }
+}
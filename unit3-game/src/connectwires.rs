@@ -13,17 +13,15 @@ use std::{thread, time};
 use strum::IntoEnumIterator; // 0.17.1
 use strum_macros::EnumIter; // 0.17.1
 
-use crate::{getSpriteFromSheet, getSpriteFromSheet_Demo, Game, GameMode, SpriteTile};
+use engine_simple::{Scene, Sprite, SpriteGroup, SpriteRange, Transition};
+
+use crate::{getSpriteFromGrid, getSpriteFromSheet_Demo, Game, SpriteTile};
 
 const W: f32 = 320.0;
 const H: f32 = 240.0;
 
 const TILE_SIZE: u16 = 16;
 
-#[cfg(target_os = "macos")]
-const SCALING_FACTOR: f32 = 5.0;
-#[cfg(target_os = "windows")]
-const SCALING_FACTOR: f32 = 2.5;
 #[derive(Debug, PartialEq, Eq, Copy, Clone, EnumIter)]
 pub enum Color {
     Pink,
@@ -264,21 +262,57 @@ pub fn initialize() -> ConnectWiresState {
     }
 }
 
-pub fn update_connect_wires(game: &mut Game, engine: &mut Engine) {
+pub struct ConnectWiresSprites {
+    bg: Sprite,
+    palette: SpriteRange,
+    squares: SpriteRange,
+}
+
+pub struct ConnectWiresScene {
+    sprites: ConnectWiresSprites,
+    groups: Vec<SpriteGroup>,
+}
+
+impl ConnectWiresScene {
+    pub fn new(game: &Game, engine: &mut Engine) -> Self {
+        let group = game.sprite_groups.connect_wires;
+        let sprites = ConnectWiresSprites {
+            bg: engine.renderer.alloc_sprite(group),
+            palette: engine.renderer.alloc_sprites(group, 0),
+            squares: engine.renderer.alloc_sprites(group, 0),
+        };
+        Self {
+            sprites,
+            groups: vec![group],
+        }
+    }
+}
+
+impl Scene<Game> for ConnectWiresScene {
+    fn update(&mut self, game: &mut Game, engine: &mut Engine) -> Transition<Game> {
+        update_connect_wires(game, engine)
+    }
+
+    fn render(&mut self, game: &mut Game, engine: &mut Engine) {
+        render_connect_wires(game, engine, &mut self.sprites)
+    }
+
+    fn sprite_groups(&self) -> &[SpriteGroup] {
+        &self.groups
+    }
+}
+
+pub fn update_connect_wires(game: &mut Game, engine: &mut Engine) -> Transition<Game> {
     if sum_correct(&game.connect_wires.correct) == 164 {
         // perform game won logic
         game.connect_wires.completed = true;
-        game.mode = GameMode::Platformer;
-        render_connect_wires(game, engine);
-        return;
+        return Transition::Pop;
     } else {
         println!("num correct: {}", sum_correct(&game.connect_wires.correct));
     }
     if engine.input.is_key_pressed(engine::Key::Escape) {
         // game intentionally abandoned
-        game.mode = GameMode::Platformer;
-        render_connect_wires(game, engine);
-        return;
+        return Transition::Pop;
     }
     if engine
         .input
@@ -287,16 +321,8 @@ pub fn update_connect_wires(game: &mut Game, engine: &mut Engine) {
         let mut remove: Vec<usize> = Vec::default();
         //let mut remove: Vec<&(SpriteTile, f32)> = Vec::default();
 
-        let window_height = engine.renderer.gpu.config.height as f32;
-        let window_witdh = engine.renderer.gpu.config.width as f32;
-
-        let mouse_pos = engine.input.mouse_pos();
-        // Normalize mouse clicks to be 00 at bottom left corner
-        let (x_norm, y_norm) = (
-            (mouse_pos.x as f32 + game.camera.screen_pos[0]) / SCALING_FACTOR,
-            (((mouse_pos.y as f32 - window_height) * (-1.0 as f32)) + game.camera.screen_pos[1])
-                / SCALING_FACTOR,
-        );
+        // Convert the click into world coordinates, 0,0 at bottom left corner
+        let mouse_world = engine.mouse_world_pos();
         let mut i = 0;
         let indicies: Vec<Vec<i16>> = vec![
             vec![7],
@@ -308,7 +334,7 @@ pub fn update_connect_wires(game: &mut Game, engine: &mut Engine) {
         let color_locs: Vec<(u16, u16)> = vec![(10, 2), (9, 3), (11, 2), (11, 3), (10, 3)];
 
         for ss_object in game.connect_wires.squares.iter() {
-            if (ss_object.0.collision.contains(x_norm, y_norm)) {
+            if (ss_object.0.collision.contains(mouse_world.x, mouse_world.y)) {
                 if ss_object.0.tex_coord.0 == 1 && ss_object.0.tex_coord.1 == 1 {
                     for color in Color::iter() {
                         if game.connect_wires.color == color {
@@ -349,7 +375,7 @@ pub fn update_connect_wires(game: &mut Game, engine: &mut Engine) {
             ];
             let mut ind: usize = 0;
             for ss_object in game.connect_wires.palette.iter() {
-                if (ss_object.0.collision.contains(x_norm, y_norm)) {
+                if (ss_object.0.collision.contains(mouse_world.x, mouse_world.y)) {
                     // check for color changes or overlap with endpoints
                     for i in 0..palette_locs.len() {
                         if ss_object.0.tex_coord.0 == palette_locs[i].0 &&  // check if sprite is a circle tex
@@ -381,30 +407,41 @@ pub fn update_connect_wires(game: &mut Game, engine: &mut Engine) {
     }
 
     if engine.input.is_key_pressed(engine::Key::S) {}
+    Transition::None
 }
 
-pub fn render_connect_wires(game: &mut Game, engine: &mut Engine) {
-    let CONNECT_WIRES_SPRITE_GROUP = 3;
-
+pub fn render_connect_wires(
+    game: &mut Game,
+    engine: &mut Engine,
+    sprites: &mut ConnectWiresSprites,
+) {
     // set bg image
-    let (trfs1, uvs1) = engine
-        .renderer
-        .sprites
-        .get_sprites_mut(CONNECT_WIRES_SPRITE_GROUP);
-    trfs1[0] = AABB::new(W / 2.0, H / 2.0, W, H).into(); // Create a non-collision AABB for use in the background
-    uvs1[0] = getSpriteFromSheet(CONNECT_WIRES_SPRITE_GROUP as u16, &(131, 62), 8, 1);
+    let (trf, uv) = engine.renderer.sprite_mut(&sprites.bg);
+    *trf = AABB::new(W / 2.0, H / 2.0, W, H).into(); // Create a non-collision AABB for use in the background
+    *uv = getSpriteFromGrid(
+        game.sprite_groups.connect_wires.index() as u16,
+        &(131, 62),
+        8,
+        1,
+    );
 
     // set palette
-    const PALETTE_START: usize = 1;
     game.connect_wires.palette.reverse();
-    for (ss_object, (trf, uv)) in game.connect_wires.palette.iter().zip(
-        trfs1[PALETTE_START..]
-            .iter_mut()
-            .zip(uvs1[PALETTE_START..].iter_mut()),
-    ) {
+    if sprites.palette.len() != game.connect_wires.palette.len() {
+        engine
+            .renderer
+            .resize_sprites(&mut sprites.palette, game.connect_wires.palette.len());
+    }
+    let (trfs, uvs) = engine.renderer.sprites_mut(&sprites.palette);
+    for (ss_object, (trf, uv)) in game
+        .connect_wires
+        .palette
+        .iter()
+        .zip(trfs.iter_mut().zip(uvs.iter_mut()))
+    {
         *trf = ss_object.0.collision.to_transform_rot(ss_object.1);
-        *uv = getSpriteFromSheet(
-            CONNECT_WIRES_SPRITE_GROUP as u16,
+        *uv = getSpriteFromGrid(
+            game.sprite_groups.connect_wires.index() as u16,
             &ss_object.0.tex_coord,
             1,
             17,
@@ -412,34 +449,26 @@ pub fn render_connect_wires(game: &mut Game, engine: &mut Engine) {
     }
 
     // set bkgd squares
-    let SQUARE_START: usize = game.connect_wires.palette.len() + 1;
-
-    for (ss_object, (trf, uv)) in game.connect_wires.squares.iter().zip(
-        trfs1[SQUARE_START..]
-            .iter_mut()
-            .zip(uvs1[SQUARE_START..].iter_mut()),
-    ) {
+    if sprites.squares.len() != game.connect_wires.squares.len() {
+        engine
+            .renderer
+            .resize_sprites(&mut sprites.squares, game.connect_wires.squares.len());
+    }
+    let (trfs, uvs) = engine.renderer.sprites_mut(&sprites.squares);
+    for (ss_object, (trf, uv)) in game
+        .connect_wires
+        .squares
+        .iter()
+        .zip(trfs.iter_mut().zip(uvs.iter_mut()))
+    {
         *trf = ss_object.0.collision.to_transform_rot(ss_object.1);
-        *uv = getSpriteFromSheet(
-            CONNECT_WIRES_SPRITE_GROUP as u16,
+        *uv = getSpriteFromGrid(
+            game.sprite_groups.connect_wires.index() as u16,
             &ss_object.0.tex_coord,
             2,
             17,
         );
     }
 
-    if !matches!(game.mode, GameMode::ConnectWires) {
-        trfs1.fill(Transform::zeroed());
-    }
-
-    engine.renderer.sprites.upload_sprites(
-        &engine.renderer.gpu,
-        CONNECT_WIRES_SPRITE_GROUP,
-        0..PALETTE_START + game.connect_wires.squares.len() + game.connect_wires.palette.len() + 1,
-    );
-
-    engine
-        .renderer
-        .sprites
-        .set_camera_all(&engine.renderer.gpu, game.camera);
+    engine.renderer.set_camera_all(game.camera);
 }
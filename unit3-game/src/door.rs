@@ -0,0 +1,217 @@
+use crate::level::{DoorAction, DoorDef, Minigame};
+use crate::SpriteTile;
+
+// (bottom, top) tiles on the sheet for each state
+pub const CLOSED_TILES: [(u16, u16); 2] = [(6, 3), (6, 2)];
+const OPEN_TILES: [(u16, u16); 2] = [(6, 1), (6, 0)];
+const LOCKED_TILES: [(u16, u16); 2] = [(5, 4), (5, 3)];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DoorState {
+    Closed,
+    Open,
+    Locked,
+}
+
+// A door in the current level. Its two tiles live in game.collision_objects
+// and get swapped out when the state changes.
+pub struct Door {
+    pub def: DoorDef,
+    pub state: DoorState,
+    // indices of the bottom and top tiles in game.collision_objects
+    pub tiles: [usize; 2],
+    // locked by a switch, and staying that way until it's flipped again
+    pub held_shut: bool,
+}
+
+impl Door {
+    pub fn new(def: DoorDef, tiles: [usize; 2]) -> Door {
+        Door {
+            def,
+            state: DoorState::Closed,
+            tiles,
+            held_shut: false,
+        }
+    }
+
+    // Back to how it starts: locked until its minigame is beaten, shut otherwise
+    pub fn reset(
+        &mut self,
+        has_requirement: impl Fn(Minigame) -> bool,
+        collision_objects: &mut [SpriteTile],
+    ) {
+        self.held_shut = false;
+        let state = if self.can_unlock(has_requirement) {
+            DoorState::Closed
+        } else {
+            DoorState::Locked
+        };
+        self.set_state(state, collision_objects);
+    }
+
+    // Keep up with the guy: unlock once `has_requirement` says the door's
+    // minigame is beaten, open as he comes near and shut again when he leaves
+    pub fn update(
+        &mut self,
+        near: bool,
+        has_requirement: impl Fn(Minigame) -> bool,
+        collision_objects: &mut [SpriteTile],
+    ) {
+        let state = match self.state {
+            DoorState::Locked if self.can_unlock(has_requirement) => DoorState::Closed,
+            DoorState::Closed if near => DoorState::Open,
+            DoorState::Open if !near => DoorState::Closed,
+            state => state,
+        };
+        if state != self.state {
+            self.set_state(state, collision_objects);
+        }
+    }
+
+    // The guy pressed space at the door. If it can open, it does and he goes
+    // through, so this gives back what the door does then.
+    pub fn try_open(
+        &mut self,
+        has_requirement: impl Fn(Minigame) -> bool,
+        collision_objects: &mut [SpriteTile],
+    ) -> Option<DoorAction> {
+        if self.state == DoorState::Locked && !self.can_unlock(has_requirement) {
+            return None;
+        }
+        self.set_state(DoorState::Open, collision_objects);
+        Some(self.def.action.clone())
+    }
+
+    // Switches and unbeaten minigames keep it locked
+    fn can_unlock(&self, has_requirement: impl Fn(Minigame) -> bool) -> bool {
+        !self.held_shut && self.def.requires.is_none_or(has_requirement)
+    }
+
+    pub fn set_state(&mut self, state: DoorState, collision_objects: &mut [SpriteTile]) {
+        self.state = state;
+        let tex_coords = match state {
+            DoorState::Closed => CLOSED_TILES,
+            DoorState::Open => OPEN_TILES,
+            DoorState::Locked => LOCKED_TILES,
+        };
+        for (idx, tex_coord) in self.tiles.iter().zip(tex_coords) {
+            collision_objects[*idx].tex_coord = tex_coord;
+        }
+    }
+
+    // Lock it if it isn't, or unlock it if it is. A door locked this way stays
+    // locked whatever minigames have been beaten.
+    pub fn toggle_lock(&mut self, collision_objects: &mut [SpriteTile]) {
+        self.held_shut = self.state != DoorState::Locked;
+        let state = if self.held_shut {
+            DoorState::Locked
+        } else {
+            DoorState::Closed
+        };
+        self.set_state(state, collision_objects);
+    }
+
+    // Locked doors block the way; the others can be walked through
+    pub fn is_solid(&self) -> bool {
+        self.state == DoorState::Locked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Region;
+    use engine_simple::geom::AABB;
+
+    // A door that plays simon says, and its two tiles
+    fn door(requires: Option<Minigame>) -> (Door, Vec<SpriteTile>) {
+        let def = DoorDef {
+            name: "door".to_string(),
+            at: (0, 0),
+            region: Region {
+                x: 0.0,
+                y: 0.0,
+                w: 32.0,
+                h: 64.0,
+            },
+            action: DoorAction::Play(Minigame::SimonSays),
+            requires,
+            sfx: None,
+        };
+        let tiles = (0..2)
+            .map(|row| SpriteTile {
+                collision: AABB::new(8.0, 8.0 + row as f32 * 16.0, 16.0, 16.0),
+                tex_coord: (0, 0),
+            })
+            .collect();
+        (Door::new(def, [0, 1]), tiles)
+    }
+
+    fn solved(minigame: Minigame) -> bool {
+        minigame == Minigame::SimonSays
+    }
+
+    fn unsolved(_: Minigame) -> bool {
+        false
+    }
+
+    #[test]
+    fn doors_open_as_the_guy_comes_near_and_shut_behind_him() {
+        let (mut door, mut tiles) = door(None);
+        door.reset(unsolved, &mut tiles);
+        assert_eq!(door.state, DoorState::Closed);
+        assert_eq!(tiles[0].tex_coord, CLOSED_TILES[0]);
+        door.update(true, unsolved, &mut tiles);
+        assert_eq!(door.state, DoorState::Open);
+        assert_eq!(tiles[1].tex_coord, OPEN_TILES[1]);
+        assert!(!door.is_solid());
+        door.update(false, unsolved, &mut tiles);
+        assert_eq!(door.state, DoorState::Closed);
+    }
+
+    #[test]
+    fn locked_doors_wait_for_their_minigame() {
+        let (mut door, mut tiles) = door(Some(Minigame::SimonSays));
+        door.reset(unsolved, &mut tiles);
+        assert_eq!(door.state, DoorState::Locked);
+        assert!(door.is_solid());
+        door.update(true, unsolved, &mut tiles);
+        assert_eq!(door.state, DoorState::Locked);
+        assert_eq!(tiles[0].tex_coord, LOCKED_TILES[0]);
+        // it unlocks first, then opens on the next update
+        door.update(true, solved, &mut tiles);
+        assert_eq!(door.state, DoorState::Closed);
+        door.update(true, solved, &mut tiles);
+        assert_eq!(door.state, DoorState::Open);
+    }
+
+    #[test]
+    fn space_only_gets_through_doors_that_can_open() {
+        let (mut door, mut tiles) = door(Some(Minigame::SimonSays));
+        door.reset(unsolved, &mut tiles);
+        assert!(door.try_open(unsolved, &mut tiles).is_none());
+        assert_eq!(door.state, DoorState::Locked);
+        let action = door.try_open(solved, &mut tiles);
+        assert!(matches!(
+            action,
+            Some(DoorAction::Play(Minigame::SimonSays))
+        ));
+        assert_eq!(door.state, DoorState::Open);
+    }
+
+    #[test]
+    fn switches_hold_doors_shut_until_flipped_back() {
+        let (mut door, mut tiles) = door(Some(Minigame::SimonSays));
+        door.reset(solved, &mut tiles);
+        assert_eq!(door.state, DoorState::Closed);
+        door.toggle_lock(&mut tiles);
+        assert_eq!(door.state, DoorState::Locked);
+        door.update(true, solved, &mut tiles);
+        assert_eq!(door.state, DoorState::Locked);
+        assert!(door.try_open(solved, &mut tiles).is_none());
+        door.toggle_lock(&mut tiles);
+        assert_eq!(door.state, DoorState::Closed);
+        door.update(true, solved, &mut tiles);
+        assert_eq!(door.state, DoorState::Open);
+    }
+}
@@ -0,0 +1,623 @@
+use engine_simple as engine;
+use engine_simple::{geom::*, Camera, Engine, SheetRegion, Transform, Zeroable};
+use engine_simple::{AnimState, Scene, Sprite, SpriteGroup, SpriteRange, Transition};
+use ron::ser::PrettyConfig;
+use winit::event::MouseButton;
+
+use crate::door::CLOSED_TILES;
+use crate::level::{DoorAction, DoorDef, Grid, Level, Region, Tiles};
+use crate::platformer;
+use crate::tiles;
+use crate::{getSpriteFromSheet, screenBox, Game, SpriteTile};
+
+const W: f32 = 320.0;
+const H: f32 = 240.0;
+
+const TILE_SIZE: u16 = 256;
+// world size of a grid cell
+const CELL: f32 = 32.0;
+// what cells hold when nothing has been painted there; the tileset makes it empty space
+const EMPTY_TILE: (u16, u16) = (9, 9);
+// on-screen size of each tile in the palette
+const PALETTE_CELL: f32 = 28.0;
+// how far the arrow keys move the view each update
+const SCROLL_SPEED: f32 = 4.0;
+
+// What clicking puts down
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Brush {
+    Tile((u16, u16)),
+    Spawn,
+    Checkpoint,
+    Door,
+}
+
+struct EditorSprites {
+    tiles: SpriteRange,
+    // the tile or door under the mouse
+    cursor: Sprite,
+    // a little guy on each spawn point and an apple in each checkpoint, plus one
+    // more of each under the mouse while placing them
+    spawns: SpriteRange,
+    checkpoints: SpriteRange,
+    palette_bg: Sprite,
+    palette: SpriteRange,
+}
+
+// Paints the current level's tiles, spawn points, checkpoints and doors with the
+// mouse, then playtests the result or saves it back to the level's file
+pub struct EditorScene {
+    // the level being edited, and its tiles (always a grid, bottom row first)
+    level: Level,
+    level_num: u16,
+    rows: Grid,
+    brush: Brush,
+    // the last tile picked, for going back to painting tiles
+    tile: (u16, u16),
+    palette_open: bool,
+    // middle of the view
+    center: Vec2,
+    // how many tiles across and down the tile sheet is
+    sheet_tiles: (u16, u16),
+    sprites: EditorSprites,
+    groups: Vec<SpriteGroup>,
+}
+
+impl EditorScene {
+    pub fn new(game: &Game, engine: &mut Engine) -> Self {
+        let rows = match platformer::levelTiles(&mut engine.assets, &game.level_data) {
+            Ok(rows) => (*rows).clone(),
+            Err(err) => {
+                eprintln!("{}", err);
+                Vec::default()
+            }
+        };
+        let tile = (1, 4);
+        let groups = &game.sprite_groups;
+        let sheet_tiles = match engine
+            .assets
+            .texture(&mut engine.renderer, tiles::SHEET_PATH)
+        {
+            Ok(tex) => {
+                let (w, h) = tex.size();
+                ((w / TILE_SIZE as u32) as u16, (h / TILE_SIZE as u32) as u16)
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                (0, 0)
+            }
+        };
+        let sprites = EditorSprites {
+            tiles: engine.renderer.alloc_sprites(groups.tiles, 0), // sized when rendering
+            cursor: engine.renderer.alloc_sprite(groups.tiles),
+            spawns: engine.renderer.alloc_sprites(groups.guy[0], 0),
+            checkpoints: engine.renderer.alloc_sprites(groups.hud, 0),
+            palette_bg: engine.renderer.alloc_sprite(groups.hud),
+            palette: engine
+                .renderer
+                .alloc_sprites(groups.tiles, (sheet_tiles.0 * sheet_tiles.1) as usize),
+        };
+        Self {
+            level: (*game.level_data).clone(),
+            level_num: game.level,
+            rows,
+            brush: Brush::Tile(tile),
+            tile,
+            palette_open: false,
+            center: game.follow_camera.center(),
+            sheet_tiles,
+            sprites,
+            groups: vec![groups.guy[0], groups.tiles, groups.hud],
+        }
+    }
+
+    fn camera(&self) -> Camera {
+        let corner = self.center - Vec2::new(W, H) / 2.0;
+        Camera {
+            screen_pos: [corner.x, corner.y],
+            screen_size: [W, H],
+        }
+    }
+
+    // The level as edited so far
+    fn edited(&self) -> Level {
+        let mut level = self.level.clone();
+        level.tiles = Tiles::Grid(self.rows.clone());
+        level
+    }
+
+    // Write the level back to its .ron file. The tiles always go out as a grid,
+    // so a level that imported an old LevelN.txt gets the tiles inlined instead,
+    // and the .txt is left as it was.
+    fn save(&self, engine: &Engine) {
+        let path = engine
+            .assets
+            .root()
+            .join(platformer::levelPath(self.level_num));
+        // one row of tiles per line
+        let config = PrettyConfig::default().depth_limit(2).struct_names(true);
+        let result = ron::ser::to_string_pretty(&self.edited(), config)
+            .map_err(|err| err.to_string())
+            .and_then(|text| std::fs::write(&path, text).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            eprintln!("couldn't save {}: {}", path.display(), err);
+        }
+    }
+
+    // The spawn point nearest the middle of the view, so playtesting starts
+    // wherever the editing was going on
+    fn playtest_spawn(&self) -> Vec2 {
+        self.level
+            .spawns
+            .values()
+            .map(|&(x, y)| Vec2::new(x, y))
+            .min_by(|a, b| {
+                a.distance(self.center)
+                    .partial_cmp(&b.distance(self.center))
+                    .unwrap()
+            })
+            .unwrap_or(self.center)
+    }
+
+    fn tile_at(&self, (col, row): (usize, usize)) -> Option<(u16, u16)> {
+        self.rows.get(row).and_then(|tiles| tiles.get(col)).copied()
+    }
+
+    // Paint a cell, growing the grid up and to the right to fit it
+    fn set_tile(&mut self, (col, row): (usize, usize), tile: (u16, u16)) {
+        if self.rows.len() <= row {
+            self.rows.resize(row + 1, Vec::default());
+        }
+        let tiles = &mut self.rows[row];
+        if tiles.len() <= col {
+            tiles.resize(col + 1, EMPTY_TILE);
+        }
+        tiles[col] = tile;
+    }
+
+    // Take out the door with a tile in `cell`, if there is one. Returns true if
+    // there was.
+    fn remove_door(&mut self, (col, row): (usize, usize)) -> bool {
+        let Some(i) = self
+            .level
+            .doors
+            .iter()
+            .position(|door| door.at.0 == col && (door.at.1 == row || door.at.1 + 1 == row))
+        else {
+            return false;
+        };
+        let door = self.level.doors.remove(i);
+        for row in [door.at.1, door.at.1 + 1] {
+            self.set_tile((door.at.0, row), EMPTY_TILE);
+        }
+        true
+    }
+
+    fn paint(&mut self, cell: (usize, usize), pos: Vec2, pressed: bool) {
+        let (col, row) = cell;
+        let corner = Vec2::new(col as f32, row as f32) * CELL;
+        match self.brush {
+            Brush::Tile(tile) => {
+                self.remove_door(cell);
+                self.set_tile(cell, tile);
+            }
+            // the rest go down once per click rather than all along a drag
+            _ if !pressed => {}
+            Brush::Spawn => {
+                let name = if self.level.spawns.is_empty() {
+                    "start".to_string()
+                } else {
+                    unusedName("spawn", |name| self.level.spawns.contains_key(name))
+                };
+                self.level.spawns.insert(name, (pos.x, pos.y));
+            }
+            Brush::Checkpoint => {
+                // two cells tall, standing on the cell clicked
+                self.level.checkpoints.push(Region {
+                    x: corner.x,
+                    y: corner.y,
+                    w: CELL,
+                    h: CELL * 2.0,
+                });
+            }
+            Brush::Door => {
+                self.remove_door(cell);
+                self.remove_door((col, row + 1));
+                let name = unusedName("door", |name| {
+                    self.level.doors.iter().any(|door| door.name == name)
+                });
+                // opens when the guy is within a cell of it; it goes back to the
+                // start until it's given somewhere else to go in the level file
+                self.level.doors.push(DoorDef {
+                    name,
+                    at: cell,
+                    region: Region {
+                        x: corner.x - CELL,
+                        y: corner.y,
+                        w: CELL * 3.0,
+                        h: CELL * 2.0,
+                    },
+                    action: DoorAction::GoTo(0, Some("start".to_string())),
+                    requires: None,
+                    sfx: None,
+                });
+                self.set_tile(cell, CLOSED_TILES[0]);
+                self.set_tile((col, row + 1), CLOSED_TILES[1]);
+            }
+        }
+    }
+
+    // Take out whatever is at `pos`: spawn points and checkpoints first, then a
+    // door or tile
+    fn erase(&mut self, cell: (usize, usize), pos: Vec2) {
+        let cell_box = cellBox(cell);
+        let spawns = self.level.spawns.len();
+        self.level
+            .spawns
+            .retain(|_, &mut (x, y)| !cell_box.contains(x, y));
+        let checkpoints = self.level.checkpoints.len();
+        self.level
+            .checkpoints
+            .retain(|region| !region.contains(pos));
+        if spawns != self.level.spawns.len() || checkpoints != self.level.checkpoints.len() {
+            return;
+        }
+        if !self.remove_door(cell) && self.tile_at(cell).is_some() {
+            self.set_tile(cell, EMPTY_TILE);
+        }
+    }
+
+    // The bottom left corner of the palette on screen
+    fn palette_corner(&self) -> Vec2 {
+        (Vec2::new(W, H) - self.palette_size()) / 2.0
+    }
+
+    // How big the palette is on screen, not counting its border
+    fn palette_size(&self) -> Vec2 {
+        Vec2::new(self.sheet_tiles.0 as f32, self.sheet_tiles.1 as f32) * PALETTE_CELL
+    }
+
+    // The sheet coordinate of the palette tile at world position `pos`
+    fn palette_tile(&self, pos: Vec2) -> Option<(u16, u16)> {
+        let screen = pos - Vec2::from(self.camera().screen_pos);
+        let offset = (screen - self.palette_corner()) / PALETTE_CELL;
+        if offset.x < 0.0 || offset.y < 0.0 {
+            return None;
+        }
+        let (col, row) = (offset.x as u16, offset.y as u16);
+        if col >= self.sheet_tiles.0 || row >= self.sheet_tiles.1 {
+            return None;
+        }
+        // the sheet's rows go down the page
+        Some((col, self.sheet_tiles.1 - 1 - row))
+    }
+}
+
+// The grid cell (column, row) that `pos` is in, if it's inside the level's corner
+fn cellAt(pos: Vec2) -> Option<(usize, usize)> {
+    if pos.x < 0.0 || pos.y < 0.0 {
+        return None;
+    }
+    Some(((pos.x / CELL) as usize, (pos.y / CELL) as usize))
+}
+
+fn cellBox((col, row): (usize, usize)) -> AABB {
+    AABB {
+        center: (Vec2::new(col as f32, row as f32) + 0.5) * CELL,
+        size: Vec2::splat(CELL),
+    }
+}
+
+// `prefix` followed by the lowest number that isn't taken yet
+fn unusedName(prefix: &str, taken: impl Fn(&str) -> bool) -> String {
+    (1..)
+        .map(|n| format!("{}{}", prefix, n))
+        .find(|name| !taken(name))
+        .unwrap()
+}
+
+impl Scene<Game> for EditorScene {
+    fn update(&mut self, game: &mut Game, engine: &mut Engine) -> Transition<Game> {
+        // leave without playing what was edited
+        if engine.input.is_key_pressed(engine::Key::Escape) {
+            return Transition::Pop;
+        }
+        if engine.input.is_key_pressed(engine::Key::Return) {
+            platformer::playLevel(game, engine, self.edited(), self.playtest_spawn());
+            return Transition::Pop;
+        }
+        if engine.input.is_key_pressed(engine::Key::S) {
+            self.save(engine);
+        }
+        if engine.input.is_key_pressed(engine::Key::Tab) {
+            self.palette_open = !self.palette_open;
+        }
+        for (key, brush) in [
+            (engine::Key::Key1, Brush::Tile(self.tile)),
+            (engine::Key::Key2, Brush::Spawn),
+            (engine::Key::Key3, Brush::Checkpoint),
+            (engine::Key::Key4, Brush::Door),
+        ] {
+            if engine.input.is_key_pressed(key) {
+                self.brush = brush;
+            }
+        }
+        self.center += Vec2::new(
+            engine.input.key_axis(engine::Key::Left, engine::Key::Right),
+            engine.input.key_axis(engine::Key::Down, engine::Key::Up),
+        ) * SCROLL_SPEED;
+
+        let mouse = engine.mouse_world_pos();
+        if self.palette_open {
+            if engine.input.is_mouse_pressed(MouseButton::Left) {
+                if let Some(tile) = self.palette_tile(mouse) {
+                    self.tile = tile;
+                    self.brush = Brush::Tile(tile);
+                    self.palette_open = false;
+                }
+            }
+            return Transition::None;
+        }
+        let Some(cell) = cellAt(mouse) else {
+            return Transition::None;
+        };
+        if engine.input.is_mouse_down(MouseButton::Left) {
+            let pressed = engine.input.is_mouse_pressed(MouseButton::Left);
+            self.paint(cell, mouse, pressed);
+        } else if engine.input.is_mouse_down(MouseButton::Right) {
+            self.erase(cell, mouse);
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, game: &mut Game, engine: &mut Engine) {
+        let camera = self.camera();
+        let view = AABB {
+            center: self.center,
+            size: Vec2::new(W, H),
+        };
+        let mouse = engine.mouse_world_pos();
+        let hovered = if self.palette_open {
+            None
+        } else {
+            cellAt(mouse)
+        };
+
+        // tiles, laid out the way the platformer will, leaving out empty space
+        let mut placed: Vec<SpriteTile> = Vec::default();
+        platformer::placeTiles(&game.tiles, &self.rows, &mut placed, &mut Vec::default());
+        let visible: Vec<&SpriteTile> = placed
+            .iter()
+            .filter(|tile| tile.tex_coord != EMPTY_TILE && view.contact(tile.collision).is_some())
+            .collect();
+        if self.sprites.tiles.len() != visible.len() {
+            engine
+                .renderer
+                .resize_sprites(&mut self.sprites.tiles, visible.len());
+        }
+        let (trfs, uvs) = engine.renderer.sprites_mut(&self.sprites.tiles);
+        for (tile, (trf, uv)) in visible.iter().zip(trfs.iter_mut().zip(uvs.iter_mut())) {
+            *trf = tile.collision.into();
+            *uv = getSpriteFromSheet(
+                &game.tiles,
+                game.sprite_groups.tiles.index() as u16,
+                &tile.tex_coord,
+                9,
+                TILE_SIZE,
+            );
+        }
+
+        // the tile (or the bottom of the door) about to be painted
+        let (trf, uv) = engine.renderer.sprite_mut(&self.sprites.cursor);
+        let preview = match self.brush {
+            Brush::Tile(tile) => Some(tile),
+            Brush::Door => Some(CLOSED_TILES[0]),
+            Brush::Spawn | Brush::Checkpoint => None,
+        };
+        match (hovered, preview) {
+            (Some(cell), Some(tile)) => {
+                *trf = cellBox(cell).into();
+                *uv = getSpriteFromSheet(
+                    &game.tiles,
+                    game.sprite_groups.tiles.index() as u16,
+                    &tile,
+                    8,
+                    TILE_SIZE,
+                );
+            }
+            _ => *trf = Transform::zeroed(),
+        }
+
+        // spawn points, as the guy's first idle frame
+        let (x, y, w, h) = game
+            .guy
+            .anim
+            .sheet()
+            .clip(AnimState::Idle)
+            .map_or((0, 0, 0, 0), |clip| clip.frames[0].rect);
+        let mut spawns: Vec<Vec2> = self
+            .level
+            .spawns
+            .values()
+            .map(|&(x, y)| Vec2::new(x, y))
+            .collect();
+        if self.brush == Brush::Spawn && hovered.is_some() {
+            spawns.push(mouse);
+        }
+        if self.sprites.spawns.len() != spawns.len() {
+            engine
+                .renderer
+                .resize_sprites(&mut self.sprites.spawns, spawns.len());
+        }
+        let (trfs, uvs) = engine.renderer.sprites_mut(&self.sprites.spawns);
+        for (pos, (trf, uv)) in spawns.iter().zip(trfs.iter_mut().zip(uvs.iter_mut())) {
+            *trf = AABB {
+                center: *pos + 3.0,
+                size: Vec2::splat(32.0),
+            }
+            .into();
+            *uv = SheetRegion::new(game.sprite_groups.guy[0].index() as u16, x, y, 8, w, h);
+        }
+
+        // checkpoints, as an apple in the middle of each
+        let mut checkpoints: Vec<Vec2> = self
+            .level
+            .checkpoints
+            .iter()
+            .map(|region| Vec2::new(region.x + region.w / 2.0, region.y + region.h / 2.0))
+            .collect();
+        if let (Brush::Checkpoint, Some(cell)) = (self.brush, hovered) {
+            checkpoints.push(cellBox(cell).center + Vec2::new(0.0, CELL / 2.0));
+        }
+        if self.sprites.checkpoints.len() != checkpoints.len() {
+            engine
+                .renderer
+                .resize_sprites(&mut self.sprites.checkpoints, checkpoints.len());
+        }
+        let (trfs, uvs) = engine.renderer.sprites_mut(&self.sprites.checkpoints);
+        for (pos, (trf, uv)) in checkpoints.iter().zip(trfs.iter_mut().zip(uvs.iter_mut())) {
+            *trf = AABB::new(pos.x, pos.y, 12.0, 12.0).into();
+            *uv = SheetRegion::new(game.sprite_groups.hud.index() as u16, 0, 496, 8, 16, 16);
+        }
+
+        // the palette: every tile on the sheet, on a panel in the middle of the screen
+        let corner = self.palette_corner();
+        let (trf, uv) = engine.renderer.sprite_mut(&self.sprites.palette_bg);
+        *trf = if self.palette_open {
+            let size = self.palette_size();
+            let middle = corner + size / 2.0;
+            screenBox(camera, middle.x, middle.y, size.x + 8.0, size.y + 8.0).into()
+        } else {
+            Transform::zeroed()
+        };
+        *uv = SheetRegion::new(game.sprite_groups.hud.index() as u16, 0, 0, 2, 640, 480);
+        let (trfs, uvs) = engine.renderer.sprites_mut(&self.sprites.palette);
+        for (i, (trf, uv)) in trfs.iter_mut().zip(uvs.iter_mut()).enumerate() {
+            let (col, row) = (i as u16 % self.sheet_tiles.0, i as u16 / self.sheet_tiles.0);
+            // sheet rows go down the page, screen rows up
+            let middle = corner
+                + (Vec2::new(col as f32, (self.sheet_tiles.1 - 1 - row) as f32) + 0.5)
+                    * PALETTE_CELL;
+            *trf = if self.palette_open {
+                screenBox(
+                    camera,
+                    middle.x,
+                    middle.y,
+                    PALETTE_CELL - 2.0,
+                    PALETTE_CELL - 2.0,
+                )
+                .into()
+            } else {
+                Transform::zeroed()
+            };
+            // the whole tile, whatever part of it gets drawn in a level
+            *uv = SheetRegion::new(
+                game.sprite_groups.tiles.index() as u16,
+                col * TILE_SIZE,
+                row * TILE_SIZE,
+                1,
+                TILE_SIZE,
+                TILE_SIZE,
+            );
+        }
+
+        engine.renderer.set_camera_all(camera);
+    }
+
+    fn sprite_groups(&self) -> &[SpriteGroup] {
+        &self.groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine_simple::Headless;
+
+    // An editor on a level with nothing in it yet
+    fn emptyEditor() -> EditorScene {
+        let mut game = Headless::<Game>::new(W as u32, H as u32);
+        let mut editor = EditorScene::new(&game.game, &mut game.engine);
+        editor.rows.clear();
+        editor.level.spawns.clear();
+        editor.level.checkpoints.clear();
+        editor.level.doors.clear();
+        editor
+    }
+
+    #[test]
+    fn painting_past_the_edge_grows_the_grid() {
+        let mut editor = emptyEditor();
+        editor.set_tile((2, 1), (1, 4));
+        assert_eq!(
+            editor.rows,
+            vec![vec![], vec![EMPTY_TILE, EMPTY_TILE, (1, 4)]]
+        );
+        // rows below only grow as far as what's painted in them
+        editor.set_tile((0, 0), (3, 4));
+        assert_eq!(editor.rows[0], vec![(3, 4)]);
+        assert_eq!(editor.tile_at((1, 0)), None);
+        assert_eq!(editor.tile_at((2, 1)), Some((1, 4)));
+    }
+
+    #[test]
+    fn removing_a_door_blanks_both_its_tiles() {
+        let mut editor = emptyEditor();
+        editor.brush = Brush::Door;
+        editor.paint((3, 1), cellBox((3, 1)).center, true);
+        assert_eq!(editor.tile_at((3, 1)), Some(CLOSED_TILES[0]));
+        assert_eq!(editor.tile_at((3, 2)), Some(CLOSED_TILES[1]));
+        assert!(!editor.remove_door((4, 1)));
+        // its top cell finds it as well as its bottom one
+        assert!(editor.remove_door((3, 2)));
+        assert!(editor.level.doors.is_empty());
+        assert_eq!(editor.tile_at((3, 1)), Some(EMPTY_TILE));
+        assert_eq!(editor.tile_at((3, 2)), Some(EMPTY_TILE));
+        assert!(!editor.remove_door((3, 1)));
+    }
+
+    #[test]
+    fn erasing_takes_markers_before_doors_and_tiles() {
+        let mut editor = emptyEditor();
+        let pos = cellBox((1, 1)).center;
+        editor.set_tile((1, 1), (3, 4));
+        editor
+            .level
+            .spawns
+            .insert("start".to_string(), (pos.x, pos.y));
+        editor.brush = Brush::Checkpoint;
+        editor.paint((1, 1), pos, true);
+
+        // the spawn point and checkpoint go first, leaving the tile
+        editor.erase((1, 1), pos);
+        assert!(editor.level.spawns.is_empty());
+        assert!(editor.level.checkpoints.is_empty());
+        assert_eq!(editor.tile_at((1, 1)), Some((3, 4)));
+        editor.erase((1, 1), pos);
+        assert_eq!(editor.tile_at((1, 1)), Some(EMPTY_TILE));
+
+        // a door goes as a whole
+        editor.brush = Brush::Door;
+        editor.paint((2, 1), cellBox((2, 1)).center, true);
+        editor.erase((2, 2), cellBox((2, 2)).center);
+        assert!(editor.level.doors.is_empty());
+        assert_eq!(editor.tile_at((2, 1)), Some(EMPTY_TILE));
+        assert_eq!(editor.tile_at((2, 2)), Some(EMPTY_TILE));
+    }
+
+    #[test]
+    fn the_palette_shows_the_sheet_the_right_way_up() {
+        let editor = emptyEditor();
+        let (cols, rows) = editor.sheet_tiles;
+        assert!(cols > 1 && rows > 1);
+        let corner = Vec2::from(editor.camera().screen_pos) + editor.palette_corner();
+        let half = Vec2::splat(PALETTE_CELL / 2.0);
+        // the bottom left of the palette is the bottom left of the sheet, whose
+        // rows count down from the top
+        assert_eq!(editor.palette_tile(corner + half), Some((0, rows - 1)));
+        let top_right = corner + editor.palette_size() - half;
+        assert_eq!(editor.palette_tile(top_right), Some((cols - 1, 0)));
+        assert_eq!(editor.palette_tile(corner - half), None);
+        assert_eq!(editor.palette_tile(top_right + PALETTE_CELL), None);
+    }
+}
@@ -0,0 +1,286 @@
+use crate::level::{EnemyDef, EnemyKind};
+use crate::SpriteGroups;
+use engine_simple::geom::*;
+use engine_simple::{AnimState, AnimationSheet, Animator, SheetRegion};
+use std::rc::Rc;
+
+pub const KINDS: [EnemyKind; 2] = [EnemyKind::Destroyer, EnemyKind::Infantryman];
+
+// shots are strips of 32x32 frames
+const SHOT_SIZE: u16 = 32;
+// updates per frame of a shot
+const SHOT_ANIM_RATE: usize = 6;
+// how long a chase goes on after losing sight of the guy
+const LOSE_SIGHT_FRAMES: usize = 90;
+const SHOT_LIFE: usize = 180;
+const GRAVITY: f32 = 1.0;
+// health the guy loses running into an enemy or getting shot
+pub const ENEMY_DAMAGE: u8 = 1;
+
+// How each kind of enemy behaves
+struct Stats {
+    size: Vec2,
+    walk_speed: f32,
+    chase_speed: f32,
+    // how far away it notices the guy
+    sight: f32,
+    // how close the guy has to be before it shoots
+    range: f32,
+    shot_speed: f32,
+    // updates between shots
+    cooldown: usize,
+}
+
+fn stats(kind: EnemyKind) -> Stats {
+    match kind {
+        EnemyKind::Destroyer => Stats {
+            size: Vec2::new(20.0, 20.0),
+            walk_speed: 0.75,
+            chase_speed: 1.25,
+            sight: 160.0,
+            range: 128.0,
+            shot_speed: 4.0,
+            cooldown: 90,
+        },
+        EnemyKind::Infantryman => Stats {
+            size: Vec2::new(16.0, 16.0),
+            walk_speed: 1.0,
+            chase_speed: 2.0,
+            sight: 128.0,
+            range: 96.0,
+            shot_speed: 5.0,
+            cooldown: 60,
+        },
+    }
+}
+
+// Its clips, relative to content/
+pub fn animationPath(kind: EnemyKind) -> String {
+    format!("{:?}/{:?}.anim.ron", kind, kind)
+}
+
+pub fn kindIndex(kind: EnemyKind) -> usize {
+    KINDS.iter().position(|k| *k == kind).unwrap()
+}
+
+// Textures for each shot group, relative to content/
+pub fn shotPaths() -> Vec<String> {
+    KINDS
+        .iter()
+        .map(|kind| format!("{:?}/Charge_1.png", kind))
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EnemyState {
+    // walking back and forth along its beat
+    Patrol,
+    // going after the guy; lost_for counts frames since it last saw him
+    Chase { lost_for: usize },
+    // standing still to fire
+    Shoot,
+    Hurt,
+    Dead,
+}
+
+// What an enemy can tell about its surroundings this frame
+pub struct Senses {
+    pub guy: Vec2,
+    pub sees_guy: bool,
+    // a wall or a drop just in front of it
+    pub blocked: bool,
+}
+
+pub struct Enemy {
+    pub def: EnemyDef,
+    pub pos: Vec2,
+    pub vel: Vec2,
+    // 1.0 facing right, -1.0 facing left
+    pub facing: f32,
+    pub state: EnemyState,
+    pub anim: Animator,
+    // frames until it can shoot again
+    pub cooldown: usize,
+}
+
+pub struct Shot {
+    pub kind: EnemyKind,
+    pub pos: Vec2,
+    pub vel: Vec2,
+    pub age: usize,
+}
+
+impl Shot {
+    pub fn is_spent(&self) -> bool {
+        self.age >= SHOT_LIFE
+    }
+
+    pub fn sheet_region(&self, groups: &SpriteGroups) -> SheetRegion {
+        let frame = (self.age / SHOT_ANIM_RATE % 4) as u16;
+        SheetRegion::new(
+            groups.shots[kindIndex(self.kind)].index() as u16,
+            frame * SHOT_SIZE,
+            0,
+            7,
+            SHOT_SIZE,
+            SHOT_SIZE,
+        )
+    }
+}
+
+impl Enemy {
+    pub fn new(def: EnemyDef, sheet: Rc<AnimationSheet>) -> Enemy {
+        Enemy {
+            pos: Vec2::new(def.at.0, def.at.1),
+            def,
+            vel: Vec2::ZERO,
+            facing: 1.0,
+            state: EnemyState::Patrol,
+            anim: Animator::new(sheet),
+            cooldown: 0,
+        }
+    }
+
+    pub fn aabb(&self) -> AABB {
+        AABB {
+            center: self.pos,
+            size: stats(self.def.kind).size,
+        }
+    }
+
+    // Where it looks from, a little above its middle
+    pub fn eye(&self) -> Vec2 {
+        self.pos + Vec2::new(0.0, stats(self.def.kind).size.y / 4.0)
+    }
+
+    pub fn sight(&self) -> f32 {
+        stats(self.def.kind).sight
+    }
+
+    pub fn is_alive(&self) -> bool {
+        !matches!(self.state, EnemyState::Hurt | EnemyState::Dead)
+    }
+
+    pub fn is_chasing(&self) -> bool {
+        matches!(self.state, EnemyState::Chase { .. } | EnemyState::Shoot)
+    }
+
+    fn set_state(&mut self, state: EnemyState) {
+        self.state = state;
+        // patrolling and chasing look like whatever its feet are doing
+        match state {
+            EnemyState::Shoot => self.anim.restart(AnimState::Attack),
+            EnemyState::Hurt => self.anim.restart(AnimState::Hurt),
+            EnemyState::Dead => self.anim.play(AnimState::Dead),
+            EnemyState::Patrol | EnemyState::Chase { .. } => (),
+        }
+    }
+
+    pub fn hurt(&mut self) {
+        if self.is_alive() {
+            self.set_state(EnemyState::Hurt);
+        }
+    }
+
+    pub fn doGravity(&mut self) {
+        if self.vel.y >= -10.0 {
+            self.vel.y -= GRAVITY;
+        }
+    }
+
+    // Run the state machine for one frame, returning a shot if it fired one
+    pub fn think(&mut self, senses: &Senses) -> Option<Shot> {
+        let stats = stats(self.def.kind);
+        let fired = self.anim.tick().iter().any(|event| event == "shoot");
+        self.cooldown = self.cooldown.saturating_sub(1);
+        let to_guy = senses.guy - self.pos;
+        let mut shot = None;
+        match self.state {
+            EnemyState::Patrol => {
+                if senses.sees_guy {
+                    self.set_state(EnemyState::Chase { lost_for: 0 });
+                    self.vel.x = 0.0;
+                    return None;
+                }
+                let (left, right) = self
+                    .def
+                    .patrol
+                    .unwrap_or((f32::NEG_INFINITY, f32::INFINITY));
+                let past_end = (self.facing > 0.0 && self.pos.x >= right)
+                    || (self.facing < 0.0 && self.pos.x <= left);
+                if senses.blocked || past_end {
+                    self.facing = -self.facing;
+                }
+                self.vel.x = self.facing * stats.walk_speed;
+            }
+            EnemyState::Chase { lost_for } => {
+                if to_guy.x != 0.0 {
+                    self.facing = to_guy.x.signum();
+                }
+                let lost_for = if senses.sees_guy { 0 } else { lost_for + 1 };
+                if lost_for > LOSE_SIGHT_FRAMES {
+                    self.set_state(EnemyState::Patrol);
+                    self.vel.x = 0.0;
+                } else if senses.sees_guy && to_guy.length() <= stats.range && self.cooldown == 0 {
+                    self.set_state(EnemyState::Shoot);
+                    self.vel.x = 0.0;
+                } else {
+                    self.state = EnemyState::Chase { lost_for };
+                    self.vel.x = if senses.blocked {
+                        0.0
+                    } else {
+                        self.facing * stats.chase_speed
+                    };
+                }
+            }
+            EnemyState::Shoot => {
+                self.vel.x = 0.0;
+                if fired {
+                    shot = Some(Shot {
+                        kind: self.def.kind,
+                        pos: self.eye() + Vec2::new(self.facing * stats.size.x / 2.0, 0.0),
+                        vel: Vec2::new(self.facing * stats.shot_speed, 0.0),
+                        age: 0,
+                    });
+                }
+                if self.anim.is_finished() {
+                    self.cooldown = stats.cooldown;
+                    self.set_state(EnemyState::Chase { lost_for: 0 });
+                }
+            }
+            EnemyState::Hurt => {
+                self.vel.x = 0.0;
+                if self.anim.is_finished() {
+                    self.set_state(EnemyState::Dead);
+                }
+            }
+            EnemyState::Dead => {
+                self.vel.x = 0.0;
+            }
+        }
+        self.anim.face(self.facing);
+        // there are no jump or fall clips, so it always counts as on the ground
+        self.anim.follow_motion(self.vel, true);
+        shot
+    }
+
+    // The frame is drawn bigger than the collision box, with their bottoms lined up
+    pub fn draw_box(&self) -> AABB {
+        let size = stats(self.def.kind).size;
+        let draw_size = size * 2.5;
+        AABB {
+            center: self.pos + Vec2::new(0.0, (draw_size.y - size.y) / 2.0),
+            size: draw_size,
+        }
+    }
+
+    // Which of its kind's textures its current frame is drawn from
+    pub fn texture(&self) -> usize {
+        self.anim.texture()
+    }
+
+    pub fn sheet_region(&self, groups: &SpriteGroups) -> SheetRegion {
+        let group = groups.enemies[kindIndex(self.def.kind)][self.texture()];
+        self.anim.sheet_region(group.index() as u16, 8)
+    }
+}
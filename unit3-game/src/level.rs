@@ -0,0 +1,237 @@
+use engine_simple::geom::Vec2;
+use engine_simple::AssetError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::Path;
+
+// A box in world coordinates, measured from its bottom left corner
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize, Serialize)]
+pub struct Region {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Region {
+    pub fn contains(&self, pos: Vec2) -> bool {
+        pos.x >= self.x && pos.x <= self.x + self.w && pos.y >= self.y && pos.y <= self.y + self.h
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub enum Minigame {
+    SimonSays,
+    ConnectWires,
+    Mining,
+}
+
+// What sets off a trigger or exit
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub enum When {
+    // the guy is inside the region
+    Enter,
+    // the guy is outside the region
+    Leave,
+    // space is pressed (inside the region, if there is one)
+    Interact,
+    // the minigame was just beaten
+    Completed(Minigame),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Trigger {
+    pub name: String,
+    #[serde(default)]
+    pub region: Option<Region>,
+    pub when: When,
+    pub play: Minigame,
+    // index into game.sfx
+    #[serde(default)]
+    pub sfx: Option<usize>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Exit {
+    pub name: String,
+    #[serde(default)]
+    pub region: Option<Region>,
+    pub when: When,
+    pub to: u16,
+    // spawn point in the target level; without one the guy stays where he is
+    #[serde(default)]
+    pub spawn: Option<String>,
+    #[serde(default)]
+    pub sfx: Option<usize>,
+}
+
+// What a door does when the guy presses space in front of it
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum DoorAction {
+    Play(Minigame),
+    // level to go to, and optionally a spawn point in it
+    GoTo(u16, Option<String>),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DoorDef {
+    pub name: String,
+    // grid cell (column, row) of the bottom half of the door, bottom row first
+    pub at: (usize, usize),
+    // the door opens while the guy is in here
+    pub region: Region,
+    pub action: DoorAction,
+    // stays locked until this minigame has been beaten
+    #[serde(default)]
+    pub requires: Option<Minigame>,
+    #[serde(default)]
+    pub sfx: Option<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub enum EnemyKind {
+    Destroyer,
+    Infantryman,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EnemyDef {
+    pub kind: EnemyKind,
+    // where it starts, in world coordinates
+    pub at: (f32, f32),
+    // left and right ends of its beat; without one it walks until it meets a wall or ledge
+    #[serde(default)]
+    pub patrol: Option<(f32, f32)>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum ObjectKind {
+    // goes from where it starts through each stop in `path` (offsets from the
+    // start) and back again, carrying whatever stands on it
+    Platform {
+        size: (f32, f32),
+        path: Vec<(f32, f32)>,
+        speed: f32,
+        // stays put until a switch turns it on
+        #[serde(default)]
+        off: bool,
+    },
+    // spins and hurts on touch; it can follow a path like a platform
+    Saw {
+        radius: f32,
+        #[serde(default)]
+        path: Vec<(f32, f32)>,
+        #[serde(default)]
+        speed: f32,
+        #[serde(default)]
+        off: bool,
+    },
+    // space flips it, and toggles the platforms, saws and doors named in `targets`
+    Switch {
+        targets: Vec<String>,
+    },
+    // a crate the guy can push around
+    Box {
+        size: f32,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ObjectDef {
+    // what switches refer to it by
+    #[serde(default)]
+    pub name: String,
+    // where its center starts, in world coordinates
+    pub at: (f32, f32),
+    pub kind: ObjectKind,
+}
+
+// Rows of sheet coordinates, bottom row first
+pub type Grid = Vec<Vec<(u16, u16)>>;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Tiles {
+    // an old style LevelN.txt grid in the Levels folder
+    Import(String),
+    Grid(Grid),
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Level {
+    #[serde(default)]
+    pub name: String,
+    pub tiles: Tiles,
+    #[serde(default)]
+    pub spawns: HashMap<String, (f32, f32)>,
+    // touching one of these moves the respawn point to where the guy is
+    #[serde(default)]
+    pub checkpoints: Vec<Region>,
+    #[serde(default)]
+    pub triggers: Vec<Trigger>,
+    #[serde(default)]
+    pub exits: Vec<Exit>,
+    #[serde(default)]
+    pub doors: Vec<DoorDef>,
+    #[serde(default)]
+    pub enemies: Vec<EnemyDef>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDef>,
+}
+
+impl Level {
+    pub fn load(path: &Path) -> Result<Level, AssetError> {
+        let text = read_to_string(path).map_err(|err| AssetError::Io(path.to_path_buf(), err))?;
+        ron::from_str(&text).map_err(|err| AssetError::Parse(path.to_path_buf(), err.to_string()))
+    }
+
+    // Just the tiles from an old LevelN.txt, with nothing else going on
+    pub fn imported(file: String) -> Level {
+        Level {
+            name: file.clone(),
+            tiles: Tiles::Import(file),
+            spawns: HashMap::default(),
+            checkpoints: Vec::default(),
+            triggers: Vec::default(),
+            exits: Vec::default(),
+            doors: Vec::default(),
+            enemies: Vec::default(),
+            objects: Vec::default(),
+        }
+    }
+
+    pub fn spawn(&self, name: &str) -> Option<Vec2> {
+        self.spawns.get(name).map(|&(x, y)| Vec2::new(x, y))
+    }
+}
+
+// Old style level: lines of (x,y) sheet coordinates, bottom row first.
+// Lines without any coordinates (like the notes in LevelTemplate.txt) are skipped.
+pub fn parseLegacyTiles(path: &Path) -> Result<Grid, AssetError> {
+    let text = read_to_string(path).map_err(|err| AssetError::Io(path.to_path_buf(), err))?;
+    let mut rows = Vec::default();
+    for line in text.lines() {
+        let mut row = Vec::default();
+        for pair in line.split('(').skip(1) {
+            let Some((inside, _)) = pair.split_once(')') else {
+                continue;
+            };
+            let Some((x, y)) = inside.split_once(',') else {
+                continue;
+            };
+            match (x.trim().parse(), y.trim().parse()) {
+                (Ok(x), Ok(y)) => row.push((x, y)),
+                _ => {
+                    return Err(AssetError::Parse(
+                        path.to_path_buf(),
+                        format!("bad tile coordinate ({})", inside),
+                    ))
+                }
+            }
+        }
+        if !row.is_empty() {
+            rows.push(row);
+        }
+    }
+    Ok(rows)
+}
@@ -5,32 +5,40 @@ use engine::Key;
 use engine_simple as engine;
 use engine_simple::wgpu;
 use engine_simple::{geom::*, Camera, Engine, SheetRegion, Transform, Zeroable};
+use engine_simple::{
+    AnimationSheet, Animator, AssetError, Assets, BitFont, Scene, SceneStack, Sprite, SpriteGroup,
+    SpriteRange, Texture, Transition,
+};
 use kira::manager::backend::DefaultBackend;
 use kira::manager::{AudioManager, AudioManagerSettings};
 use kira::sound::static_sound::{StaticSoundData, StaticSoundSettings};
+use level::Level;
 use mining::MiningState;
 use rand::Rng;
 use simonsays::SimonSaysState;
 use std::f32::RADIX;
+use std::rc::Rc;
+use tiles::Tileset;
 use winit::platform;
 // use std::os::windows::fs::FileTypeExt;
 use kira;
 use std::fs::read_to_string;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::{thread, time};
 
 mod connectwires;
+mod door;
+mod editor;
+mod enemy;
+mod level;
 mod mining;
+mod movement;
+mod objects;
 mod platformer;
 mod simonsays;
+mod tiles;
 const W: f32 = 320.0;
 const H: f32 = 240.0;
-const SPRITE_MAX: usize = 128;
-
-const TOP_HALF_COLLISION: [(u16, u16); 4] = [(0, 3), (1, 3), (2, 3), (3, 3)];
-// const BOT_HALF_COLLISION: [(u16, u16); 2] = [(0,0), (2,2)];
-// const DEATH_COLLISION: [(u16, u16); 2] = [(0,0), (2,2)];
-// const DOOR_COLLISION: [(u16, u16); 6] = [(6,0), (6,1), (6,2), (6,3), (5,3), (5,4)];
 
 // const LEFT: &'static [&'static str] = &["Hello", "World", "!"];
 
@@ -43,76 +51,223 @@ pub struct SpriteTile {
     tex_coord: (u16, u16),
 }
 
+// The sprite groups made in Game::new, for whatever draws from their textures
+pub struct SpriteGroups {
+    // one per texture the guy's clips draw from; the first is also what the
+    // HUD draws him from
+    pub guy: Vec<SpriteGroup>,
+    // the tile sheet, for the platformer
+    pub tiles: SpriteGroup,
+    pub simon_says: SpriteGroup,
+    pub connect_wires: SpriteGroup,
+    pub mining: SpriteGroup,
+    // per enemy kind in enemy::KINDS order, one per texture its clips draw from
+    pub enemies: Vec<Vec<SpriteGroup>>,
+    // per enemy kind, the one its shots are drawn from
+    pub shots: Vec<SpriteGroup>,
+    // demo.png, for the HUD and its font
+    pub hud: SpriteGroup,
+    // one per objects::OBJECT_TEXTURES
+    pub objects: Vec<SpriteGroup>,
+}
+
 pub struct Game {
     camera: engine::Camera,
+    // what the platformer shows; the minigames use the fixed camera above
+    follow_camera: engine::FollowCamera,
+    // shake, zoom and pans on top of follow_camera
+    camera_effects: engine::CameraEffects,
     collision_objects: Vec<SpriteTile>,
-    doors: Vec<u16>,
+    // tiles in collision_objects that belong to doors
+    door_tiles: Vec<usize>,
+    // indices of the solid tiles in collision_objects, by where they are
+    wall_grid: SpatialGrid<usize>,
+    doors: Vec<door::Door>,
+    enemies: Vec<enemy::Enemy>,
+    // platforms, saws, switches and boxes
+    objects: Vec<objects::Object>,
+    // index in objects of what the guy is standing on, which carries him along
+    riding: Option<usize>,
+    // each kind of enemy's clips, in enemy::KINDS order
+    enemy_anims: Vec<Rc<AnimationSheet>>,
+    // enemy shots in flight
+    shots: Vec<enemy::Shot>,
+    // minigames beaten so far
+    solved: Vec<level::Minigame>,
     guy: platformer::Guy,
+    // how the guy runs and jumps
+    movement: Rc<movement::Movement>,
     level: u16,
-    mode: GameMode,
-    sfx_manager: AudioManager,
+    level_data: Rc<Level>,
+    scenes: SceneStack<Game>,
+    sprite_groups: SpriteGroups,
+    // None if there's no audio device
+    sfx_manager: Option<AudioManager>,
     sfx: Vec<StaticSoundData>,
+    tiles: Rc<Tileset>,
     simon_says: SimonSaysState,
-    // spin_saws_objects: Vec<(SpriteTile, u16)>,
     connect_wires: ConnectWiresState,
     mining: MiningState,
 }
 
-enum GameMode {
-    Platformer,
-    SimonSays,
-    ConnectWires,
-    Mining,
-    // Other modes...
+// Freezes whatever is underneath until P is pressed again
+struct PauseScene;
+
+impl Scene<Game> for PauseScene {
+    fn update(&mut self, _game: &mut Game, engine: &mut Engine) -> Transition<Game> {
+        if engine.input.is_key_pressed(engine::Key::P) {
+            return Transition::Pop;
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, _game: &mut Game, _engine: &mut Engine) {}
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// Shown over the platformer once the last life is gone; space starts a new game
+pub struct GameOverScene {
+    panel: Sprite,
+    lives: SpriteRange,
+    font: BitFont,
+    groups: Vec<SpriteGroup>,
 }
 
-fn newSpriteGroup(sprite_path: &str, engine: &mut Engine, camera_ref: &Camera) {
+impl GameOverScene {
+    pub fn new(game: &Game, engine: &mut Engine) -> Self {
+        let hud = game.sprite_groups.hud;
+        Self {
+            panel: engine.renderer.alloc_sprite(hud),
+            lives: engine.renderer.alloc_sprites(hud, 1),
+            font: hudFont(hud),
+            groups: vec![hud],
+        }
+    }
+}
+
+impl Scene<Game> for GameOverScene {
+    fn update(&mut self, game: &mut Game, engine: &mut Engine) -> Transition<Game> {
+        if engine.input.is_key_pressed(engine::Key::Space) {
+            platformer::newGame(game, engine);
+            return Transition::Pop;
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, game: &mut Game, engine: &mut Engine) {
+        // a plain panel from demo.png's background with a big zero for the lives
+        // left, in the middle of wherever the platformer's camera is
+        let camera = engine.renderer.camera();
+        let (trf, uv) = engine.renderer.sprite_mut(&self.panel);
+        *trf = screenBox(camera, W / 2.0, H / 2.0, 96.0, 64.0).into();
+        *uv = SheetRegion::new(game.sprite_groups.hud.index() as u16, 0, 0, 2, 640, 480);
+        let text_box = screenBox(camera, W / 2.0 - 12.0, H / 2.0 - 16.0, 0.0, 32.0);
+        engine.renderer.draw_text(
+            &self.font,
+            &self.lives,
+            "0",
+            text_box.center,
+            text_box.size.y,
+        );
+    }
+
+    fn sprite_groups(&self) -> &[SpriteGroup] {
+        &self.groups
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// Where a box centered at (x, y) on the W by H screen is in the world, seen
+// through `camera`, so HUD pieces stay put however it scrolls or zooms
+pub fn screenBox(camera: Camera, x: f32, y: f32, w: f32, h: f32) -> AABB {
+    let scale = Vec2::from(camera.screen_size) / Vec2::new(W, H);
+    AABB {
+        center: Vec2::from(camera.screen_pos) + Vec2::new(x, y) * scale,
+        size: Vec2::new(w, h) * scale,
+    }
+}
+
+// The digits along the bottom of demo.png, drawn from sprite group `hud`
+pub fn hudFont(hud: SpriteGroup) -> BitFont {
+    BitFont::with_sheet_region(
+        '0'..='9',
+        SheetRegion::new(hud.index() as u16, 0, 512, 0, 80, 8),
+        10,
+    )
+}
+
+fn newSpriteGroup(sprite_path: &str, engine: &mut Engine, camera_ref: &Camera) -> SpriteGroup {
+    // the same sheet is only uploaded once however many groups draw from it
+    let sprite_tex = engine
+        .assets
+        .texture(&mut engine.renderer, sprite_path)
+        .unwrap_or_else(|err| panic!("{}", err));
+    addSpriteGroup(&sprite_tex, engine, camera_ref)
+}
+
+fn addSpriteGroup(sprite_tex: &Texture, engine: &mut Engine, camera_ref: &Camera) -> SpriteGroup {
     let camera = camera_ref.clone();
 
-    let sprite_img = image::open(sprite_path).unwrap().into_rgba8();
+    // empty to start with; the groups grow as scenes allocate sprites in them
+    engine
+        .renderer
+        .add_sprite_group(sprite_tex, Vec::default(), Vec::default(), camera)
+}
+
+// Play sound effect `which`, if there's anything to play it on
+pub fn playSfx(game: &mut Game, which: usize) {
+    if let Some(sfx_manager) = &mut game.sfx_manager {
+        let _ = sfx_manager.play(game.sfx[which].clone());
+    }
+}
 
-    let sprite_tex = engine.renderer.gpu.create_texture(
-        &sprite_img,
-        wgpu::TextureFormat::Rgba8UnormSrgb,
-        sprite_img.dimensions(),
-        Some(sprite_path), // Some string or something
-    );
+fn loadSound(path: &Path) -> Result<StaticSoundData, AssetError> {
+    StaticSoundData::from_file(path, StaticSoundSettings::default())
+        .map_err(|err| AssetError::Parse(path.to_path_buf(), err.to_string()))
+}
 
-    engine.renderer.sprites.add_sprite_group(
-        &engine.renderer.gpu,
-        &sprite_tex,
-        vec![Transform::zeroed(); SPRITE_MAX], //bg, three walls, guy, a few apples
-        vec![SheetRegion::zeroed(); SPRITE_MAX],
-        camera,
-    );
+// content/ next to wherever we're run from, falling back to the one in the source tree
+fn contentDir() -> PathBuf {
+    let local = Path::new("content");
+    if local.is_dir() {
+        local.to_path_buf()
+    } else {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("content")
+    }
 }
 
+// Region of a tile on the tile sheet, cropped to the part the tileset says to draw
 fn getSpriteFromSheet(
+    tiles: &Tileset,
     sheet_num: u16,
     tex_coord: &(u16, u16),
     depth: u16,
     sprite_size: u16,
 ) -> SheetRegion {
-    if TOP_HALF_COLLISION.contains(tex_coord) {
-        SheetRegion::new(
-            sheet_num,
-            tex_coord.0 * sprite_size,
-            tex_coord.1 * sprite_size,
-            depth,
-            sprite_size,
-            sprite_size / 2,
-        )
-    } else {
-        // *trf = (wall.collision).into();
-        SheetRegion::new(
-            sheet_num,
-            tex_coord.0 * sprite_size,
-            tex_coord.1 * sprite_size,
-            depth,
-            sprite_size,
-            sprite_size,
-        )
-    }
+    tiles.sheet_region(sheet_num, tex_coord, depth, sprite_size)
+}
+
+// Whole grid cell, for sheets with no tile properties (the puzzle tiles)
+fn getSpriteFromGrid(
+    sheet_num: u16,
+    tex_coord: &(u16, u16),
+    depth: u16,
+    sprite_size: u16,
+) -> SheetRegion {
+    SheetRegion::new(
+        sheet_num,
+        tex_coord.0 * sprite_size,
+        tex_coord.1 * sprite_size,
+        depth,
+        sprite_size,
+        sprite_size,
+    )
 }
 
 // Meant to just get it directly based on data
@@ -155,29 +310,94 @@ impl engine::Game for Game {
             screen_size: [W, H],
         };
 
-        newSpriteGroup("content/Swordsman/swordsman_sheet.png", engine, &camera); // 0
-        newSpriteGroup("content/new_spritesheet.png", engine, &camera); // 1 (for platformer)
-        newSpriteGroup("content/new_spritesheet.png", engine, &camera); // 2 (for simon says)
-        newSpriteGroup("content/puzzle_tiles.png", engine, &camera); // 3 (for connect wires)
-        newSpriteGroup("content/puzzle_tiles.png", engine, &camera); // 4 (for mining)
+        let guy_anims = engine
+            .assets
+            .animation(&mut engine.renderer, platformer::GUY_ANIMATIONS)
+            .unwrap_or_else(|err| panic!("{}", err));
+        let enemy_anims: Vec<Rc<AnimationSheet>> = enemy::KINDS
+            .iter()
+            .map(|kind| {
+                engine
+                    .assets
+                    .animation(&mut engine.renderer, enemy::animationPath(*kind))
+                    .unwrap_or_else(|err| panic!("{}", err))
+            })
+            .collect();
+
+        let guy_group = addSpriteGroup(&guy_anims.textures[0], engine, &camera); // swordsman_sheet.png
+        let tile_group = newSpriteGroup(tiles::SHEET_PATH, engine, &camera);
+        let simon_says_group = newSpriteGroup(tiles::SHEET_PATH, engine, &camera);
+        let connect_wires_group = newSpriteGroup("puzzle_tiles.png", engine, &camera);
+        let mining_group = newSpriteGroup("puzzle_tiles.png", engine, &camera);
+        let mut enemy_groups = Vec::default();
+        for sheet in enemy_anims.iter() {
+            let mut groups = Vec::default();
+            for tex in sheet.textures.iter() {
+                groups.push(addSpriteGroup(tex, engine, &camera));
+            }
+            enemy_groups.push(groups);
+        }
+        let mut shot_groups = Vec::default();
+        for path in enemy::shotPaths() {
+            shot_groups.push(newSpriteGroup(&path, engine, &camera));
+        }
+        // getting hurt and dying
+        let mut guy_groups = vec![guy_group];
+        for tex in guy_anims.textures[1..].iter() {
+            guy_groups.push(addSpriteGroup(tex, engine, &camera));
+        }
+        let sprite_groups = SpriteGroups {
+            guy: guy_groups,
+            tiles: tile_group,
+            simon_says: simon_says_group,
+            connect_wires: connect_wires_group,
+            mining: mining_group,
+            enemies: enemy_groups,
+            shots: shot_groups,
+            hud: newSpriteGroup("demo.png", engine, &camera),
+            objects: objects::OBJECT_TEXTURES
+                .iter()
+                .map(|path| newSpriteGroup(path, engine, &camera))
+                .collect(),
+        };
 
         //newSpriteGroup("content/Objects/DoorUnlocked.png", engine, &camera); // 2
 
+        let tiles = engine
+            .assets
+            .load(tiles::TILESET_PATH, Tileset::load)
+            .unwrap_or_else(|err| panic!("{}", err));
+        let movement = engine
+            .assets
+            .load(movement::MOVEMENT_PATH, movement::Movement::load)
+            .unwrap_or_else(|err| panic!("{}", err));
         let mut collision_objects: Vec<SpriteTile> = Vec::default();
-        let mut doors: Vec<u16> = Vec::default();
-        platformer::loadLevel(&mut collision_objects, &mut doors, 0);
+        let mut door_tiles: Vec<usize> = Vec::default();
+        let level_data = platformer::loadLevel(
+            &mut engine.assets,
+            &tiles,
+            &mut collision_objects,
+            &mut door_tiles,
+            0,
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
 
-        let mut sfx_manager =
-            AudioManager::<DefaultBackend>::new(AudioManagerSettings::default()).unwrap();
+        // without an audio device (on a build server, say) the game runs silently
+        let sfx_manager = match AudioManager::<DefaultBackend>::new(AudioManagerSettings::default())
+        {
+            Ok(sfx_manager) => Some(sfx_manager),
+            Err(err) => {
+                eprintln!("no sound: {}", err);
+                None
+            }
+        };
         let mut sfx: Vec<StaticSoundData> = Vec::default();
         for i in 0..=8 {
-            sfx.push(
-                StaticSoundData::from_file(
-                    format!("content/SFX/beep{}.wav", i),
-                    StaticSoundSettings::default(),
-                )
-                .unwrap(),
-            );
+            let sound = engine
+                .assets
+                .load(format!("SFX/beep{}.wav", i), loadSound)
+                .unwrap_or_else(|err| panic!("{}", err));
+            sfx.push((*sound).clone());
         }
 
         let guy = platformer::Guy {
@@ -187,11 +407,20 @@ impl engine::Game for Game {
             },
             vel: Vec2 { x: 0.0, y: 0.0 },
             grounded: false,
-            frame: 0,
+            anim: Animator::new(guy_anims),
+            coyote: 0,
+            jump_buffer: 0,
+            jumping: false,
             respawn_pos: Vec2 {
                 x: W / 2.0,
                 y: H / 4.0,
             },
+            facing: 1.0,
+            state: platformer::GuyState::Normal,
+            timer: 0,
+            invulnerable: 0,
+            health: platformer::GUY_MAX_HEALTH,
+            lives: platformer::GUY_LIVES,
         };
 
         //              size_x
@@ -205,39 +434,189 @@ impl engine::Game for Game {
         //     10,
         // );
 
-        Game {
+        let mut game = Game {
             camera,
+            follow_camera: platformer::followCamera(),
+            camera_effects: engine::CameraEffects::default(),
             guy,
+            movement,
             collision_objects,
-            doors,
+            door_tiles,
+            wall_grid: SpatialGrid::new(platformer::WALL_CELL_SIZE),
+            doors: Vec::default(),
+            enemies: Vec::default(),
+            objects: Vec::default(),
+            riding: None,
+            enemy_anims,
+            shots: Vec::default(),
+            solved: Vec::default(),
             level: 0,
-            mode: GameMode::Platformer,
+            level_data,
+            scenes: SceneStack::default(),
+            sprite_groups,
             sfx_manager,
             sfx,
-            simon_says: simonsays::initialize(),
+            tiles,
+            simon_says: simonsays::initialize(&mut engine.rng),
             connect_wires: connectwires::initialize(),
-            mining: mining::initialize(),
-        }
+            mining: mining::initialize(&mut engine.rng),
+        };
+        platformer::spawnDoors(&mut game, engine);
+        platformer::indexWalls(&mut game);
+        platformer::spawnEnemies(&mut game);
+        platformer::spawnObjects(&mut game);
+        game.follow_camera.bounds = platformer::levelBounds(&game);
+        game.follow_camera.snap_to(game.guy.pos);
+        let mut scenes = SceneStack::default();
+        scenes.push(
+            Box::new(platformer::PlatformerScene::new(&game, engine)),
+            &mut game,
+            engine,
+        );
+        game.scenes = scenes;
+        game
     }
 
+    // The scene stack is taken out of the game while it runs, since scenes
+    // need the rest of the game as their shared state
     fn update(&mut self, engine: &mut Engine) {
-        match self.mode {
-            GameMode::Platformer => platformer::update_platformer(self, engine),
-            GameMode::ConnectWires => connectwires::update_connect_wires(self, engine),
-            GameMode::SimonSays => simonsays::update_simon_says(self, engine),
-            GameMode::Mining => mining::update_mining(self, engine),
-        }
+        let mut scenes = std::mem::take(&mut self.scenes);
+        scenes.update(self, engine);
+        self.scenes = scenes;
     }
 
     fn render(&mut self, engine: &mut Engine) {
-        match self.mode {
-            GameMode::Platformer => platformer::render_platformer(self, engine),
-            GameMode::ConnectWires => connectwires::render_connect_wires(self, engine),
-            GameMode::SimonSays => simonsays::render_simon_says(self, engine),
-            GameMode::Mining => mining::render_mining(self, engine),
-        }
+        let mut scenes = std::mem::take(&mut self.scenes);
+        scenes.render(self, engine);
+        self.scenes = scenes;
     }
 }
 fn main() {
-    Engine::new(winit::window::WindowBuilder::new()).run::<Game>();
+    let mut engine = Engine::new(winit::window::WindowBuilder::new());
+    engine.assets = Assets::new(contentDir());
+    // edit levels and sprite sheets while the game runs
+    if let Err(err) = engine.assets.watch() {
+        eprintln!("{}", err);
+    }
+    // cargo run -- --record session.json / --replay session.json
+    let args: Vec<String> = std::env::args().collect();
+    match (args.get(1).map(String::as_str), args.get(2)) {
+        (Some("--record"), Some(path)) => engine.record_to(path).unwrap(),
+        (Some("--replay"), Some(path)) => engine.replay_from(path).unwrap(),
+        _ => (),
+    }
+    engine.run::<Game>();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine_simple::Headless;
+
+    // The game as it starts, without a window, audio or GPU
+    fn headlessGame() -> Headless<Game> {
+        Headless::new(W as u32, H as u32)
+    }
+
+    #[test]
+    fn holding_left_runs_the_guy_left() {
+        let mut game = headlessGame();
+        // let him land first
+        game.step(60);
+        let start = game.game.guy.pos;
+        assert!(game.game.guy.grounded);
+
+        // not so far that he reaches the ramp
+        game.press_key(Key::Left);
+        game.step(10);
+        let guy = &game.game.guy;
+        assert!(guy.pos.x < start.x - 10.0);
+        assert_eq!(guy.pos.y, start.y);
+        // and he's drawn where he is now
+        let group = game.game.sprite_groups.guy[guy.anim.texture()];
+        let (trfs, _uvs) = game.engine.renderer.uploaded_sprites(group);
+        assert!(trfs
+            .iter()
+            .any(|trf| trf.w == 32 && trf.x == guy.pos.x + 3.0 && trf.y == guy.pos.y + 3.0));
+    }
+
+    #[test]
+    fn the_guy_walks_up_the_ramps_in_the_first_room() {
+        let mut game = headlessGame();
+        game.step(60);
+        let start = game.game.guy.pos;
+        game.press_key(Key::Left);
+        game.step(20);
+        // partway up the shallow ramp
+        let guy = &game.game.guy;
+        assert!(guy.grounded);
+        assert!(guy.pos.y > start.y + 8.0);
+        game.step(40);
+        // and over the ledge, up the steep one against the left wall
+        let guy = &game.game.guy;
+        assert!(guy.grounded);
+        assert!(guy.pos.x < 64.0);
+        assert!(guy.pos.y > start.y + 48.0);
+    }
+
+    #[test]
+    fn enemies_are_drawn_from_their_kinds_groups() {
+        let mut game = headlessGame();
+        platformer::take_exit(&mut game.game, &mut game.engine, 2, Some("entrance"));
+        game.step(1);
+        let enemy = &game.game.enemies[0];
+        let kind = enemy::kindIndex(enemy.def.kind);
+        let group = game.game.sprite_groups.enemies[kind][enemy.texture()];
+        let (trfs, uvs) = game.engine.renderer.uploaded_sprites(group);
+        assert!(trfs[0].w > 0);
+        assert_eq!(uvs[0].sheet, group.index() as u16);
+    }
+
+    #[test]
+    fn objects_are_drawn_from_their_textures_groups() {
+        let mut game = headlessGame();
+        platformer::take_exit(&mut game.game, &mut game.engine, 7, None);
+        game.step(1);
+        assert!(!game.game.objects.is_empty());
+        for object in game.game.objects.iter() {
+            let Some(texture) = object.texture() else {
+                continue;
+            };
+            // find its sprite wherever it landed in the group
+            let drawn = object.transform();
+            let group = game.game.sprite_groups.objects[texture];
+            let (trfs, uvs) = game.engine.renderer.uploaded_sprites(group);
+            let slot = trfs
+                .iter()
+                .position(|trf| trf.w > 0 && trf.x == drawn.x && trf.y == drawn.y)
+                .expect("object isn't drawn in its texture's group");
+            assert_eq!(uvs[slot].sheet, group.index() as u16);
+        }
+    }
+
+    #[test]
+    fn simon_says_draws_its_board_over_the_platformer() {
+        let mut game = headlessGame();
+        game.step(1);
+        let (trfs, _uvs) = game
+            .engine
+            .renderer
+            .uploaded_sprites(game.game.sprite_groups.simon_says);
+        assert!(trfs.iter().all(|trf| trf.w == 0));
+
+        game.press_key(Key::S);
+        game.step(1);
+        game.release_key(Key::S);
+        game.step(1);
+        let (trfs, _uvs) = game
+            .engine
+            .renderer
+            .uploaded_sprites(game.game.sprite_groups.simon_says);
+        assert!(trfs.iter().any(|trf| trf.w > 0));
+        // the platformer underneath is hidden
+        for group in game.game.sprite_groups.guy.iter() {
+            let (trfs, _uvs) = game.engine.renderer.uploaded_sprites(*group);
+            assert!(trfs.iter().all(|trf| trf.w == 0));
+        }
+    }
 }
@@ -3,20 +3,20 @@ use engine_simple as engine;
 use engine_simple::wgpu;
 use engine_simple::{geom::*, Camera, Engine, SheetRegion, Transform, Zeroable};
 use rand::Rng;
-use std::thread::sleep;
 use std::time::Duration;
 use std::{thread, time};
 
-use crate::{getSpriteFromSheet, getSpriteFromSheet_Demo, Game, GameMode, SpriteTile};
+use engine_simple::{Scene, Sprite, SpriteGroup, SpriteRange, Timer, Transition};
+
+use crate::{getSpriteFromGrid, getSpriteFromSheet, getSpriteFromSheet_Demo, Game, SpriteTile};
 
 const W: f32 = 320.0;
 const H: f32 = 240.0;
 
 const TILE_SIZE: u16 = 256;
-#[cfg(target_os = "macos")]
-const SCALING_FACTOR: f32 = 5.0;
-#[cfg(target_os = "windows")]
-const SCALING_FACTOR: f32 = 2.5;
+
+// how long after a hit before the next click counts
+const HIT_COOLDOWN: Duration = time::Duration::from_millis(10);
 
 pub struct MiningState {
     pub obstacles: Vec<(SpriteTile, f32)>, //Vec of sprites and their rotation
@@ -24,6 +24,7 @@ pub struct MiningState {
     pub prize: (f32, f32),
     pub grid: Vec<(f32, f32, i32)>,
     pub prize_hits: usize,
+    pub hit_cooldown: Timer,
 }
 
 fn newSpriteTile_Square(pos_x: f32, pos_y: f32, size: f32, tex_x: u16, tex_y: u16) -> SpriteTile {
@@ -47,7 +48,7 @@ fn newSpriteTile_Rect(
     }
 }
 
-pub fn initialize() -> MiningState {
+pub fn initialize(rng: &mut impl Rng) -> MiningState {
     let mut obstacles = Vec::default();
 
     let mut x_pos = 13.0;
@@ -56,10 +57,10 @@ pub fn initialize() -> MiningState {
 
     let mut grid = Vec::default(); //x,y,hits taken (up to 3)
                                    // generate prize behind ice
-    let rand_x = rand::thread_rng().gen_range(0..=12) as f32 * 25.0 + x_pos;
-    let rand_y = y_pos - rand::thread_rng().gen_range(0..=8) as f32 * 25.0;
+    let rand_x = rng.gen_range(0..=12) as f32 * 25.0 + x_pos;
+    let rand_y = y_pos - rng.gen_range(0..=8) as f32 * 25.0;
     let obj_tex_coords = vec![(12, 0), (13, 1), (14, 1), (14, 2), (14, 5), (13, 5)];
-    let obj_rand = obj_tex_coords[rand::thread_rng().gen_range(0..5)];
+    let obj_rand = obj_tex_coords[rng.gen_range(0..5)];
     obstacles.push((
         newSpriteTile_Square(rand_x, rand_y, 23.0, obj_rand.0, obj_rand.1),
         0.0,
@@ -81,35 +82,72 @@ pub fn initialize() -> MiningState {
         prize: (rand_x, rand_y),
         grid,
         prize_hits: 0,
+        hit_cooldown: Timer::after(HIT_COOLDOWN),
     }
 }
 
-pub fn update_mining(game: &mut Game, engine: &mut Engine) {
-    if engine.input.is_key_pressed(engine::Key::S) {
-        game.mode = GameMode::Platformer;
-        if !matches!(game.mode, GameMode::Mining) {
-            println!("here");
-            render_mining(game, engine);
+pub struct MiningSprites {
+    bg: Sprite,
+    prize: Sprite,
+    ice: SpriteRange,
+}
+
+pub struct MiningScene {
+    sprites: MiningSprites,
+    groups: Vec<SpriteGroup>,
+}
+
+impl MiningScene {
+    pub fn new(game: &Game, engine: &mut Engine) -> Self {
+        let (tiles, mining) = (game.sprite_groups.tiles, game.sprite_groups.mining);
+        let sprites = MiningSprites {
+            bg: engine.renderer.alloc_sprite(tiles),
+            prize: engine.renderer.alloc_sprite(mining),
+            ice: engine.renderer.alloc_sprites(mining, 0),
+        };
+        Self {
+            sprites,
+            groups: vec![tiles, mining],
         }
     }
+}
+
+impl Scene<Game> for MiningScene {
+    fn on_enter(&mut self, game: &mut Game, engine: &mut Engine) {
+        // new prize location every time
+        game.mining = initialize(&mut engine.rng);
+    }
+
+    fn update(&mut self, game: &mut Game, engine: &mut Engine) -> Transition<Game> {
+        update_mining(game, engine)
+    }
+
+    fn render(&mut self, game: &mut Game, engine: &mut Engine) {
+        render_mining(game, engine, &mut self.sprites)
+    }
+
+    fn sprite_groups(&self) -> &[SpriteGroup] {
+        &self.groups
+    }
+}
+
+pub fn update_mining(game: &mut Game, engine: &mut Engine) -> Transition<Game> {
+    if engine.input.is_key_pressed(engine::Key::S) {
+        return Transition::Pop;
+    }
 
     println!("{}, {}", game.mining.prize.0, game.mining.prize.1);
+    game.mining.hit_cooldown.tick();
     if engine
         .input
         .is_mouse_pressed(winit::event::MouseButton::Left)
+        && game.mining.hit_cooldown.is_finished()
     {
-        let window_height = engine.renderer.gpu.config.height as f32;
-        let window_witdh = engine.renderer.gpu.config.width as f32;
-        let mouse_pos = engine.input.mouse_pos();
-        // Normalize mouse clicks to be 00 at bottom left corner
-        let (x_norm, y_norm) = (
-            (mouse_pos.x as f32 + game.camera.screen_pos[0]) / SCALING_FACTOR,
-            (((mouse_pos.y as f32 - window_height) * (-1.0 as f32)) + game.camera.screen_pos[1])
-                / SCALING_FACTOR,
-        );
+        // Convert the click into world coordinates, 0,0 at bottom left corner
+        let mouse_world = engine.mouse_world_pos();
         let mut overPrize = false;
         for (idx, ss_object) in game.mining.obstacles.iter_mut().enumerate() {
-            if (ss_object.0.collision.contains(x_norm, y_norm)) {
+            if (ss_object.0.collision.contains(mouse_world.x, mouse_world.y)) {
                 if idx == 0 {
                     if game.mining.prize_hits < 3 {
                         game.mining.prize_hits += 1;
@@ -120,8 +158,7 @@ pub fn update_mining(game: &mut Game, engine: &mut Engine) {
                     } else {
                         game.mining.completed = true;
                         game.mining.prize_hits = 0;
-                        game.mode = GameMode::Platformer;
-                        return;
+                        return Transition::Pop;
                     }
                 }
                 match ss_object.0.tex_coord {
@@ -130,43 +167,39 @@ pub fn update_mining(game: &mut Game, engine: &mut Engine) {
                     (7, 12) => ss_object.0.collision.size = Vec2::new(0.0, 0.0),
                     _ => continue,
                 }
-                //freeze for a split second to make sure that clicks have to be separated
-                sleep(time::Duration::from_millis(10));
+                //wait a split second to make sure that clicks have to be separated
+                game.mining.hit_cooldown.reset();
             }
         }
     }
+    Transition::None
 }
 
-pub fn render_mining(game: &mut Game, engine: &mut Engine) {
-    let DEMO_SPRITE_GROUP = 0;
-    let TILE_SPRITE_GROUP = 1;
-    let MINING_SPRITE_GROUP = 4;
-
+pub fn render_mining(game: &mut Game, engine: &mut Engine, sprites: &mut MiningSprites) {
     // set bg image
-    let (trfs1, uvs1) = engine.renderer.sprites.get_sprites_mut(TILE_SPRITE_GROUP);
-    trfs1[0] = AABB::new(W / 2.0, H / 2.0, W, H).into(); // Create a non-collision AABB for use in the background
-    uvs1[0] = getSpriteFromSheet(TILE_SPRITE_GROUP as u16, &(2, 4), 16, TILE_SIZE);
-
-    if !matches!(game.mode, GameMode::Mining) {
-        trfs1.fill(Transform::zeroed());
-    }
+    let (trf, uv) = engine.renderer.sprite_mut(&sprites.bg);
+    *trf = AABB::new(W / 2.0, H / 2.0, W, H).into(); // Create a non-collision AABB for use in the background
+    *uv = getSpriteFromSheet(
+        &game.tiles,
+        game.sprite_groups.tiles.index() as u16,
+        &(2, 4),
+        16,
+        TILE_SIZE,
+    );
 
-    // set walls
-    const WALL_START: usize = 1;
-    // set up variables for walls
-    let (trfs0, uvs0) = engine.renderer.sprites.get_sprites_mut(MINING_SPRITE_GROUP);
     // prize placement
-    trfs0[WALL_START] = game.mining.obstacles[0].0.collision.into();
+    let (trf, uv) = engine.renderer.sprite_mut(&sprites.prize);
+    *trf = game.mining.obstacles[0].0.collision.into();
     if game.mining.prize_hits >= 3 {
-        uvs0[WALL_START] = getSpriteFromSheet(
-            MINING_SPRITE_GROUP as u16,
+        *uv = getSpriteFromGrid(
+            game.sprite_groups.mining.index() as u16,
             &game.mining.obstacles[0].0.tex_coord,
             13,
             17,
         );
     } else {
-        uvs0[WALL_START] = getSpriteFromSheet(
-            MINING_SPRITE_GROUP as u16,
+        *uv = getSpriteFromGrid(
+            game.sprite_groups.mining.index() as u16,
             &game.mining.obstacles[0].0.tex_coord,
             15,
             17,
@@ -174,45 +207,22 @@ pub fn render_mining(game: &mut Game, engine: &mut Engine) {
     }
 
     // ice placement
-    let ice_idx = WALL_START + 2;
-    for (ss_object, (trf, uv)) in game.mining.obstacles[1..game.mining.obstacles.len()]
-        .iter()
-        .zip(trfs0[ice_idx..].iter_mut().zip(uvs0[ice_idx..].iter_mut()))
-    {
+    let ice = &game.mining.obstacles[1..];
+    if sprites.ice.len() != ice.len() {
+        engine.renderer.resize_sprites(&mut sprites.ice, ice.len());
+    }
+    let (trfs, uvs) = engine.renderer.sprites_mut(&sprites.ice);
+    for (ss_object, (trf, uv)) in ice.iter().zip(trfs.iter_mut().zip(uvs.iter_mut())) {
         *trf = ss_object.0.collision.to_transform_rot(ss_object.1);
-        *uv = getSpriteFromSheet(MINING_SPRITE_GROUP as u16, &ss_object.0.tex_coord, 14, 17);
+        *uv = getSpriteFromGrid(
+            game.sprite_groups.mining.index() as u16,
+            &ss_object.0.tex_coord,
+            14,
+            17,
+        );
 
         //*uv = getSpriteFromSheet(TILE_SPRITE_GROUP as u16, &ss_object.0.tex_coord, 12, TILE_SIZE);
     }
 
-    // set guy
-    // let (trfs, uvs) = engine.renderer.sprites.get_sprites_mut(DEMO_SPRITE_GROUP);
-    // let guy_idx = WALL_START + game.collision_objects.len();
-
-    // trfs[guy_idx] = AABB {
-    //     center: game.guy.pos + 3.0,
-    //     size: Vec2 { x: 32.0, y: 32.0 },
-    // }
-    // .into();
-    // // animate guy
-    // uvs[guy_idx] = getSpriteFromSheet_Demo(DEMO_SPRITE_GROUP as u16, GUY_FRAMES[game.guy.frame].0 + 24, GUY_FRAMES[game.guy.frame].1 + 44, 8, 100, 100);
-
-    // engine
-    //     .renderer
-    //     .sprites
-    //     .upload_sprites(&engine.renderer.gpu, DEMO_SPRITE_GROUP, 0..WALL_START + game.collision_objects.len() + 1);
-    engine.renderer.sprites.upload_sprites(
-        &engine.renderer.gpu,
-        MINING_SPRITE_GROUP,
-        0..game.mining.obstacles.len() + WALL_START + 2,
-    );
-    engine.renderer.sprites.upload_sprites(
-        &engine.renderer.gpu,
-        TILE_SPRITE_GROUP,
-        0..game.collision_objects.len() + 1,
-    );
-    engine
-        .renderer
-        .sprites
-        .set_camera_all(&engine.renderer.gpu, game.camera);
+    engine.renderer.set_camera_all(game.camera);
 }
@@ -0,0 +1,38 @@
+use engine_simple::AssetError;
+use serde::Deserialize;
+use std::fs::read_to_string;
+use std::path::Path;
+
+pub const MOVEMENT_PATH: &str = "movement.ron";
+
+// How the guy moves, tuned in content/movement.ron. Speeds are in pixels per
+// update and times in updates.
+#[derive(Clone, Copy, PartialEq, Debug, Deserialize)]
+pub struct Movement {
+    // top running speed
+    pub max_speed: f32,
+    // speed gained per update while a direction is held
+    pub acceleration: f32,
+    // speed lost per update when nothing is held
+    pub friction: f32,
+    // share of acceleration and friction he gets in the air, 0 to 1
+    pub air_control: f32,
+    // upward speed a jump starts with
+    pub jump_speed: f32,
+    // letting go of jump while rising cuts his speed to this share of jump_speed
+    pub jump_cut: f32,
+    pub gravity: f32,
+    // fastest he can fall
+    pub terminal_velocity: f32,
+    // how long after running off a ledge he can still jump
+    pub coyote_frames: usize,
+    // how long before landing a jump press still counts
+    pub jump_buffer_frames: usize,
+}
+
+impl Movement {
+    pub fn load(path: &Path) -> Result<Movement, AssetError> {
+        let text = read_to_string(path).map_err(|err| AssetError::Io(path.to_path_buf(), err))?;
+        ron::from_str(&text).map_err(|err| AssetError::Parse(path.to_path_buf(), err.to_string()))
+    }
+}
@@ -0,0 +1,187 @@
+use crate::level::{ObjectDef, ObjectKind};
+use crate::SpriteGroups;
+use engine_simple::geom::*;
+use engine_simple::{SheetRegion, Transform};
+
+// Relative to content/: a saw, a switch that's on, one that's off, and a box.
+// Each gets its own sprite group; platforms are drawn from the tile sheet instead.
+pub const OBJECT_TEXTURES: [&str; 4] = [
+    "Objects/Saw.png",
+    "Objects/Switch (1).png",
+    "Objects/Switch (2).png",
+    "Objects/Box.png",
+];
+const SAW_TEXTURE: usize = 0;
+const SWITCH_ON_TEXTURE: usize = 1;
+const SWITCH_OFF_TEXTURE: usize = 2;
+const BOX_TEXTURE: usize = 3;
+// the part of each texture that gets drawn; switches leave off their cable
+const TEXTURE_RECTS: [(u16, u16, u16, u16); 4] = [
+    (0, 0, 357, 361),
+    (0, 0, 72, 94),
+    (0, 0, 72, 94),
+    (0, 0, 256, 256),
+];
+
+// platforms look like the thin platform tiles
+pub const PLATFORM_TILE: (u16, u16) = (1, 3);
+const SWITCH_SIZE: Vec2 = Vec2::new(12.0, 16.0);
+// radians a saw turns each update
+const SAW_SPIN: f32 = 0.3;
+// health the guy loses touching a saw
+pub const SAW_DAMAGE: u8 = 1;
+const GRAVITY: f32 = 1.0;
+const TERMINAL_VELOCITY: f32 = 11.0;
+
+// One of the current level's objects
+pub struct Object {
+    pub def: ObjectDef,
+    pub pos: Vec2,
+    pub vel: Vec2,
+    // how far it went last update, so whatever is standing on it can come along
+    pub moved: Vec2,
+    // platforms and saws only move while on; switches are on once flipped
+    pub on: bool,
+    // the stop on its path it's heading for, and whether it's on the way back
+    stop: usize,
+    returning: bool,
+    // how far a saw has spun
+    angle: f32,
+}
+
+impl Object {
+    pub fn new(def: ObjectDef) -> Object {
+        let on = match def.kind {
+            ObjectKind::Platform { off, .. } | ObjectKind::Saw { off, .. } => !off,
+            ObjectKind::Switch { .. } | ObjectKind::Box { .. } => false,
+        };
+        Object {
+            pos: Vec2::new(def.at.0, def.at.1),
+            vel: Vec2::ZERO,
+            moved: Vec2::ZERO,
+            on,
+            stop: 1,
+            returning: false,
+            angle: 0.0,
+            def,
+        }
+    }
+
+    // Platforms and boxes block the guy; saws and switches don't
+    pub fn is_solid(&self) -> bool {
+        matches!(
+            self.def.kind,
+            ObjectKind::Platform { .. } | ObjectKind::Box { .. }
+        )
+    }
+
+    pub fn is_box(&self) -> bool {
+        matches!(self.def.kind, ObjectKind::Box { .. })
+    }
+
+    pub fn aabb(&self) -> AABB {
+        let size = match self.def.kind {
+            ObjectKind::Platform { size, .. } => Vec2::new(size.0, size.1),
+            ObjectKind::Saw { radius, .. } => Vec2::splat(radius * 2.0),
+            ObjectKind::Switch { .. } => SWITCH_SIZE,
+            ObjectKind::Box { size } => Vec2::splat(size),
+        };
+        AABB {
+            center: self.pos,
+            size,
+        }
+    }
+
+    // The blade, if this is a saw
+    pub fn saw(&self) -> Option<Circle> {
+        match self.def.kind {
+            ObjectKind::Saw { radius, .. } => Some(Circle::new(self.pos.x, self.pos.y, radius)),
+            _ => None,
+        }
+    }
+
+    // What flipping this switch toggles
+    pub fn targets(&self) -> &[String] {
+        match &self.def.kind {
+            ObjectKind::Switch { targets } => targets,
+            _ => &[],
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.on = !self.on;
+    }
+
+    // Move platforms and saws one update along their paths, going back and forth
+    // between the first stop (where they start) and the last, and spin saws
+    pub fn travel(&mut self) {
+        self.moved = Vec2::ZERO;
+        let (path, speed) = match &self.def.kind {
+            ObjectKind::Platform { path, speed, .. } => (path, *speed),
+            ObjectKind::Saw { path, speed, .. } => {
+                if self.on {
+                    self.angle += SAW_SPIN;
+                }
+                (path, *speed)
+            }
+            ObjectKind::Switch { .. } | ObjectKind::Box { .. } => return,
+        };
+        if !self.on || path.is_empty() {
+            return;
+        }
+        let start = Vec2::new(self.def.at.0, self.def.at.1);
+        let goal = match self.stop {
+            0 => start,
+            stop => start + Vec2::new(path[stop - 1].0, path[stop - 1].1),
+        };
+        let to_goal = goal - self.pos;
+        if to_goal.length() > speed {
+            self.moved = to_goal.normalize() * speed;
+            self.pos += self.moved;
+            return;
+        }
+        self.moved = to_goal;
+        self.pos = goal;
+        // turn around at either end
+        if (self.returning && self.stop == 0) || (!self.returning && self.stop == path.len()) {
+            self.returning = !self.returning;
+        }
+        if self.returning {
+            self.stop -= 1;
+        } else {
+            self.stop += 1;
+        }
+    }
+
+    pub fn doGravity(&mut self) {
+        self.vel.y = (self.vel.y - GRAVITY).max(-TERMINAL_VELOCITY);
+    }
+
+    // Which of OBJECT_TEXTURES it's drawn from; platforms use the tile sheet instead
+    pub fn texture(&self) -> Option<usize> {
+        let texture = match self.def.kind {
+            ObjectKind::Platform { .. } => return None,
+            ObjectKind::Saw { .. } => SAW_TEXTURE,
+            ObjectKind::Switch { .. } if self.on => SWITCH_ON_TEXTURE,
+            ObjectKind::Switch { .. } => SWITCH_OFF_TEXTURE,
+            ObjectKind::Box { .. } => BOX_TEXTURE,
+        };
+        Some(texture)
+    }
+
+    pub fn sheet_region(&self, groups: &SpriteGroups, depth: u16) -> SheetRegion {
+        let Some(texture) = self.texture() else {
+            return SheetRegion::new(0, 0, 0, depth, 0, 0);
+        };
+        let (x, y, w, h) = TEXTURE_RECTS[texture];
+        SheetRegion::new(groups.objects[texture].index() as u16, x, y, depth, w, h)
+    }
+
+    // Where it's drawn; saws turn as they spin
+    pub fn transform(&self) -> Transform {
+        match self.saw() {
+            Some(saw) => saw.to_transform_rot(self.angle),
+            None => self.aabb().into(),
+        }
+    }
+}
@@ -9,716 +9,1509 @@ use std::f32::RADIX;
 // use std::os::windows::fs::FileTypeExt;
 use std::fs::read_to_string;
 use std::path::Path;
+use std::rc::Rc;
 use std::{thread, time};
 // use bytemuck::{Pod, Zeroable};
 
+use engine_simple::{
+    AnimState, AnimationSheet, Animator, AssetError, Assets, BitFont, FollowCamera, Scene, Sprite,
+    SpriteGroup, SpriteRange, Transition,
+};
+
+use crate::connectwires::ConnectWiresScene;
+use crate::door::Door;
+use crate::editor::EditorScene;
+use crate::enemy::{self, Enemy, Senses};
+use crate::level::{parseLegacyTiles, DoorAction, Grid, Level, Minigame, Region, Tiles, When};
+use crate::mining::MiningScene;
+use crate::movement::{self, Movement};
+use crate::objects::{self, Object};
+use crate::simonsays::SimonSaysScene;
+use crate::tiles::{self, Collision, Hazard, Tileset};
 use crate::{
-    getSpriteFromSheet, getSpriteFromSheet_Demo, mining, newSpriteTile_Rect, newSpriteTile_Square,
-    Game, GameMode, SpriteTile,
+    connectwires, getSpriteFromSheet, hudFont, mining, newSpriteTile_Rect, newSpriteTile_Square,
+    playSfx, screenBox, simonsays, Game, GameOverScene, PauseScene, SpriteTile,
 };
 
 const W: f32 = 320.0;
 const H: f32 = 240.0;
-const GUY_HORZ_SPEED: f32 = 2.5; //4.0
-const COLLISION_STEPS: usize = 3;
-const GRAVITY: f32 = 1.0;
-const NO_COLLISION: u16 = 9;
-
-const TOP_HALF_COLLISION: [(u16, u16); 7] =
-    [(0, 3), (1, 3), (2, 3), (3, 3), (3, 2), (4, 2), (5, 2)];
-const BOT_HALF_COLLISION: [(u16, u16); 2] = [(0, 0), (2, 2)];
-const DEATH_COLLISION: [(u16, u16); 2] = [(0, 0), (2, 2)];
-const DOOR_COLLISION: [(u16, u16); 6] = [(6, 0), (6, 1), (6, 2), (6, 3), (5, 3), (5, 4)];
-
-// Each sprite is 128.5 wide and 130 tall but lots of blank space around character.
-// index 0-7 walking to the right
-// index 8-15 walking to the left
-// index 16-20 is idle
-const GUY_FRAMES: [(u16, u16); 26] = [
-    (0, 390),
-    (128, 390),
-    (256, 390),
-    (384, 390),
-    (512, 390),
-    (640, 390),
-    (769, 390),
-    (898, 390),
-    (0, 520),
-    (128, 520),
-    (256, 520),
-    (385, 520),
-    (513, 520),
-    (641, 520),
-    (769, 520),
-    (898, 520),
-    (0, 260),
-    (0, 260),
-    (128, 260),
-    (128, 260),
-    (256, 260),
-    (256, 260),
-    (385, 260),
-    (385, 260),
-    (0, 260),
-    (0, 260),
-];
 
 // const LEFT: &'static [&'static str] = &["Hello", "World", "!"];
 
 const TILE_SIZE: u16 = 256;
+// how big a tile is in the world
+const TILE_WORLD_SIZE: f32 = 32.0;
+
+// a couple of tiles across, so the guy only ever looks at a few cells
+pub const WALL_CELL_SIZE: f32 = 64.0;
+
+// the guy can move this far (each way) from the middle of the screen before
+// the camera follows, and it looks this many updates of his velocity ahead
+const CAMERA_DEAD_ZONE: Vec2 = Vec2::new(24.0, 32.0);
+const CAMERA_LOOK_AHEAD: f32 = 12.0;
+// share of the way to its goal the camera moves each update
+const CAMERA_SMOOTHING: f32 = 0.15;
+// shaking: how hard getting hurt and dying rattle the screen (trauma, 0 to 1)
+const HURT_TRAUMA: f32 = 0.4;
+const DEATH_TRAUMA: f32 = 0.9;
+// the camera closes in on him while he dies
+const DEATH_ZOOM: f32 = 1.5;
+const DEATH_ZOOM_FRAMES: usize = 45;
+// updates to ease over to a new room or his respawn point
+const CAMERA_PAN_FRAMES: usize = 40;
+
+// how far below its feet a slope can fall away and still keep something that
+// was standing on it there, so walking downhill isn't a string of little drops
+const SLOPE_SNAP: f32 = 4.0;
+// how far above its feet a slope can rise in one update and still be stepped onto
+const SLOPE_STEP: f32 = 8.0;
+// feet this close below the top of a one-way platform still land on it
+const ONE_WAY_TOLERANCE: f32 = 0.5;
+
+// The guy's clips, relative to content/
+pub const GUY_ANIMATIONS: &str = "Swordsman/guy.anim.ron";
+// lie there a moment after the death animation before respawning
+const GUY_DEAD_PAUSE: usize = 30;
+// updates the guy can't be hurt again for after taking a hit
+const GUY_INVULNERABLE: usize = 90;
+const GUY_KNOCKBACK: Vec2 = Vec2::new(3.0, 5.0);
+pub const GUY_MAX_HEALTH: u8 = 3;
+pub const GUY_LIVES: u8 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GuyState {
+    Normal,
+    Attacking,
+    // knocked back and not in control for a moment
+    Hurt,
+    Dead,
+}
 
 pub struct Guy {
     pub pos: Vec2,
     pub vel: Vec2,
     pub grounded: bool,
-    pub frame: usize,
+    pub anim: Animator,
     pub respawn_pos: Vec2,
+    // updates left that he can still jump after leaving the ground
+    pub coyote: usize,
+    // updates left that a jump press is waiting to happen
+    pub jump_buffer: usize,
+    // rising from a jump that letting go of the key can still cut short
+    pub jumping: bool,
+    // 1.0 facing right, -1.0 facing left
+    pub facing: f32,
+    pub state: GuyState,
+    // updates since the current state started
+    pub timer: usize,
+    // updates left before he can be hurt again
+    pub invulnerable: usize,
+    pub health: u8,
+    // tries left, counting this one; losing the last is game over
+    pub lives: u8,
 }
 
 impl Guy {
-    pub fn doGravity(&mut self) {
-        if self.vel.y >= -10.0 {
-            self.vel.y -= GRAVITY;
-        }
+    pub fn doGravity(&mut self, movement: &Movement) {
+        self.vel.y = (self.vel.y - movement.gravity).max(-movement.terminal_velocity);
     }
 
-    pub fn setHorzVel(&mut self, direction: f32) {
-        self.vel.x = direction * GUY_HORZ_SPEED;
+    // Speed up towards `direction`, or slow down if it's zero
+    pub fn setHorzVel(&mut self, direction: f32, movement: &Movement) {
+        let target = direction * movement.max_speed;
+        let mut rate = if direction != 0.0 {
+            movement.acceleration
+        } else {
+            movement.friction
+        };
+        if !self.grounded {
+            rate *= movement.air_control;
+        }
+        self.vel.x = if self.vel.x < target {
+            (self.vel.x + rate).min(target)
+        } else {
+            (self.vel.x - rate).max(target)
+        };
     }
 
-    pub fn handle_jump(&mut self, vert_dir: f32) {
-        if vert_dir > 0.0 && self.grounded {
-            self.vel.y = 10.0;
+    // Jump if he's on the ground (or only just left it) and jump was pressed
+    // just now (or only just before landing). Returns true if he jumped.
+    pub fn handle_jump(
+        &mut self,
+        jump_pressed: bool,
+        jump_held: bool,
+        movement: &Movement,
+    ) -> bool {
+        if self.grounded {
+            self.coyote = movement.coyote_frames;
+            self.jumping = false;
+        } else {
+            self.coyote = self.coyote.saturating_sub(1);
+        }
+        if jump_pressed {
+            self.jump_buffer = movement.jump_buffer_frames;
+        } else {
+            self.jump_buffer = self.jump_buffer.saturating_sub(1);
+        }
+        // letting go early makes for a shorter hop
+        if self.jumping && !jump_held && self.vel.y > 0.0 {
+            self.vel.y = self.vel.y.min(movement.jump_speed * movement.jump_cut);
+            self.jumping = false;
+        }
+        if self.jump_buffer > 0 && (self.grounded || self.coyote > 0) {
+            self.vel.y = movement.jump_speed;
             self.grounded = false;
+            self.jumping = true;
+            self.coyote = 0;
+            self.jump_buffer = 0;
+            return true;
         }
+        false
+    }
+
+    // Forget any jump in progress or waiting to happen
+    fn stop_jumping(&mut self) {
+        self.coyote = 0;
+        self.jump_buffer = 0;
+        self.jumping = false;
     }
 
     pub fn set_respawn(&mut self) {
         self.respawn_pos = self.pos;
     }
 
-    pub fn moveGuy(&mut self, horz_dir: f32, vert_dir: f32) {
-        //Handle velocities
-        self.setHorzVel(horz_dir);
-        self.handle_jump(vert_dir);
-        self.doGravity();
-
-        // Update positon
-        self.pos.x += self.vel.x;
-        self.pos.y += self.vel.y;
+    fn set_state(&mut self, state: GuyState) {
+        self.state = state;
+        self.timer = 0;
+        match state {
+            // moveGuy picks idle, walking and so on from how he's moving
+            GuyState::Normal => self.anim.play(AnimState::Idle),
+            GuyState::Attacking => self.anim.restart(AnimState::Attack),
+            GuyState::Hurt => self.anim.restart(AnimState::Hurt),
+            GuyState::Dead => self.anim.restart(AnimState::Dead),
+        }
+    }
 
-        //Handle animation
-        if horz_dir == 0.0 || vert_dir != 0.0 {
-            // use idle frames when still or jumping
-            if self.frame == 25 || self.frame < 16 {
-                // restart animation
-                self.frame = 16;
-            } else {
-                self.frame += 1;
+    // Returns true if he jumped
+    pub fn moveGuy(
+        &mut self,
+        horz_dir: f32,
+        jump_pressed: bool,
+        jump_held: bool,
+        movement: &Movement,
+    ) -> bool {
+        let mut jumped = false;
+        self.timer += 1;
+        self.invulnerable = self.invulnerable.saturating_sub(1);
+        self.anim.tick();
+        match self.state {
+            GuyState::Normal => {
+                //Handle velocities
+                self.setHorzVel(horz_dir, movement);
+                jumped = self.handle_jump(jump_pressed, jump_held, movement);
+                if horz_dir != 0.0 {
+                    self.facing = horz_dir.signum();
+                }
             }
-        } else if horz_dir > 0.0 {
-            // if walking to the right
-            if self.frame == 7 || self.frame > 8 {
-                // if frames recycle OR was not previously walking right
-                self.frame = 0;
-            } else {
-                self.frame += 1;
+            GuyState::Attacking => {
+                // plant his feet until the swing is over
+                self.vel.x = 0.0;
+                if self.anim.is_finished() {
+                    self.set_state(GuyState::Normal);
+                }
             }
-        } else if horz_dir < 0.0 {
-            // if walking to the left
-            if self.frame == 15 || self.frame > 15 || self.frame < 7 {
-                // if frames recycle OR was not previously walking left
-                self.frame = 8;
-            } else {
-                self.frame += 1;
+            GuyState::Hurt => {
+                // the knockback carries him for a bit
+                if self.anim.is_finished() {
+                    self.set_state(GuyState::Normal);
+                }
             }
+            GuyState::Dead => {
+                self.vel.x = 0.0;
+                // the pause before respawning starts once he's down
+                if !self.anim.is_finished() {
+                    self.timer = 0;
+                }
+            }
+        }
+        self.doGravity(movement);
+        // the position is moved by update_platformer, which knows where the walls are
+
+        //Handle animation
+        self.anim.face(self.facing);
+        self.anim.follow_motion(self.vel, self.grounded);
+        jumped
+    }
+
+    pub fn attack(&mut self) {
+        if self.state == GuyState::Normal {
+            self.set_state(GuyState::Attacking);
+        }
+    }
+
+    // Where the swing can hit something this frame, if anywhere
+    pub fn attack_box(&self) -> Option<AABB> {
+        if self.state != GuyState::Attacking || !self.anim.has_event("hit") {
+            return None;
+        }
+        Some(AABB {
+            center: self.pos + Vec2::new(self.facing * 16.0, 2.0),
+            size: Vec2::new(20.0, 16.0),
+        })
+    }
+
+    // Lose `damage` health and get knocked back away from `from`, or die if that
+    // was the last of it. Returns false if he couldn't be hurt right now.
+    pub fn hurt(&mut self, from: Vec2, damage: u8) -> bool {
+        if self.state == GuyState::Dead || self.invulnerable > 0 {
+            return false;
         }
+        self.health = self.health.saturating_sub(damage);
+        if self.health == 0 {
+            self.kill();
+            return true;
+        }
+        let away = if self.pos.x < from.x { -1.0 } else { 1.0 };
+        self.vel = Vec2::new(away * GUY_KNOCKBACK.x, GUY_KNOCKBACK.y);
+        self.grounded = false;
+        self.invulnerable = GUY_INVULNERABLE;
+        self.set_state(GuyState::Hurt);
+        true
+    }
+
+    // Start the death animation; update_platformer respawns him once it's over
+    pub fn kill(&mut self) {
+        if self.state != GuyState::Dead {
+            self.set_state(GuyState::Dead);
+        }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.state == GuyState::Dead
+    }
+
+    pub fn death_finished(&self) -> bool {
+        self.is_dead() && self.anim.is_finished() && self.timer >= GUY_DEAD_PAUSE
     }
 
     pub fn die(&mut self) {
         self.pos = self.respawn_pos;
+        self.vel = Vec2::ZERO;
+        self.stop_jumping();
+        self.invulnerable = 0;
+        self.health = GUY_MAX_HEALTH;
+        self.set_state(GuyState::Normal);
+    }
+
+    // Start over at a spawn point, which becomes the new respawn point
+    pub fn spawn_at(&mut self, pos: Vec2) {
+        self.pos = pos;
+        self.vel = Vec2::ZERO;
+        self.grounded = false;
+        self.stop_jumping();
+        self.respawn_pos = pos;
+        self.invulnerable = 0;
+        self.health = GUY_MAX_HEALTH;
+        self.set_state(GuyState::Normal);
     }
 }
 
-pub fn loadLevel(collision_objects: &mut Vec<SpriteTile>, doors: &mut Vec<u16>, num: u16) {
-    let mut x_pos: f32 = 16.0;
-    let mut y_pos: f32 = 16.0;
-    let size: f32 = 32.0;
-    // let incr: f32 = 32.0;
+pub fn levelPath(num: u16) -> String {
+    format!("Levels/Level{}.ron", num)
+}
 
-    let binding = read_to_string(format!("content/Levels/Level{}.txt", num)).unwrap();
-    // let str_tex_coords = binding
-    //     .split(|c| c == ' ')
-    //     .filter(|&s| !s.is_empty());
+// The tile rows a level uses, reading old .txt grids through the asset cache too
+pub fn levelTiles(assets: &mut Assets, level: &Level) -> Result<Rc<Grid>, AssetError> {
+    match &level.tiles {
+        Tiles::Grid(rows) => Ok(Rc::new(rows.clone())),
+        Tiles::Import(file) => assets.load(format!("Levels/{}", file), parseLegacyTiles),
+    }
+}
 
-    let segments: Vec<&str> = binding
-        .split(|c| c == ' ' || c == '\n' || c == '(' || c == ')')
-        .filter(|s| !s.is_empty())
-        // .rev()
-        .collect();
+pub fn loadLevel(
+    assets: &mut Assets,
+    tiles: &Tileset,
+    collision_objects: &mut Vec<SpriteTile>,
+    door_tiles: &mut Vec<usize>,
+    num: u16,
+) -> Result<Rc<Level>, AssetError> {
+    // let incr: f32 = 32.0;
 
-    for segment in segments.iter() {
-        //println!("{}", segment);
-    }
-
-    // let tex_coords: Vec<(u16,u16)> = segments.iter()
-    //     .for_each(|tex_coord| tex_coord.split(",") );
-
-    let mut tex_coords: Vec<(u16, u16)> = Vec::default();
-
-    for str_tex_coord in segments {
-        let s: String = String::from(str_tex_coord);
-        if s.chars().nth(0).unwrap().is_ascii_digit() {
-            //println!("tex_coords: {}, {}", s.chars().nth(0).unwrap(), s.chars().nth(2).unwrap());
-            tex_coords.push((
-                s.chars().nth(0).unwrap() as u16 - 48,
-                s.chars().nth(2).unwrap() as u16 - 48,
-            ));
-        }
-    }
-    //println!("{:?}", tex_coords);
-
-    for tex_coord in tex_coords {
-        if TOP_HALF_COLLISION.contains(&tex_coord) {
-            collision_objects.push(newSpriteTile_Rect(
-                x_pos,
-                y_pos + (size / 4.0),
-                size,
-                size / 2.0,
-                tex_coord.0,
-                tex_coord.1,
-            ));
-        } else if BOT_HALF_COLLISION.contains(&tex_coord) {
-            collision_objects.push(newSpriteTile_Rect(
-                x_pos,
-                y_pos - (size / 4.0),
-                size,
-                size / 2.0,
-                tex_coord.0,
-                tex_coord.1,
-            ));
-        } else if DOOR_COLLISION.contains(&tex_coord) {
-            doors.push(collision_objects.len() as u16);
-            collision_objects.push(newSpriteTile_Square(
-                x_pos,
-                y_pos,
-                size,
-                tex_coord.0,
-                tex_coord.1,
-            ));
-        } else {
-            collision_objects.push(newSpriteTile_Square(
-                x_pos,
-                y_pos,
-                size,
-                tex_coord.0,
-                tex_coord.1,
-            ));
-        }
-
-        if x_pos == 16.0 + size * 9.0 {
-            x_pos = 16.0;
-            y_pos += size;
-        } else {
+    let level = match assets.load(levelPath(num), Level::load) {
+        Ok(level) => level,
+        // levels from before there was metadata are just a tile grid
+        Err(AssetError::Missing(_)) => Rc::new(Level::imported(format!("Level{}.txt", num))),
+        Err(err) => return Err(err),
+    };
+    let rows = levelTiles(assets, &level)?;
+    placeTiles(tiles, &rows, collision_objects, door_tiles);
+    Ok(level)
+}
+
+// Lay out a grid of tiles as collision objects, bottom row first
+pub fn placeTiles(
+    tiles: &Tileset,
+    rows: &[Vec<(u16, u16)>],
+    collision_objects: &mut Vec<SpriteTile>,
+    door_tiles: &mut Vec<usize>,
+) {
+    let size: f32 = TILE_WORLD_SIZE;
+    let mut y_pos: f32 = 16.0;
+    for row in rows.iter() {
+        let mut x_pos: f32 = 16.0;
+        for &tex_coord in row.iter() {
+            let props = tiles.get(&tex_coord);
+            if props.door {
+                door_tiles.push(collision_objects.len());
+            }
+            if props.collision == Collision::TopHalf {
+                collision_objects.push(newSpriteTile_Rect(
+                    x_pos,
+                    y_pos + (size / 4.0),
+                    size,
+                    size / 2.0,
+                    tex_coord.0,
+                    tex_coord.1,
+                ));
+            } else if props.collision == Collision::BottomHalf {
+                collision_objects.push(newSpriteTile_Rect(
+                    x_pos,
+                    y_pos - (size / 4.0),
+                    size,
+                    size / 2.0,
+                    tex_coord.0,
+                    tex_coord.1,
+                ));
+            } else {
+                collision_objects.push(newSpriteTile_Square(
+                    x_pos,
+                    y_pos,
+                    size,
+                    tex_coord.0,
+                    tex_coord.1,
+                ));
+            }
             x_pos += size;
         }
+        y_pos += size;
     }
     // collision_objects.reverse();
 }
 
-fn move_to_level(game: &mut Game, level_num: u16) {
+fn move_to_level(game: &mut Game, engine: &mut Engine, level_num: u16) {
     game.level = level_num;
     game.collision_objects.clear();
-    game.doors.clear();
-    loadLevel(&mut game.collision_objects, &mut game.doors, game.level);
-}
-
-fn level_handling(game: &mut Game, engine: &mut Engine, guy_aabb: &AABB) {
-    // check if guy collides with doors
-    // Level 0 is start
-    // Level 1 and 3 are level 0 but doors open
-    // Level 2 is next room
-
-    match game.level {
-        // Starting Area
-        0 => {
-            if guy_aabb.center.x > 250.0 && guy_aabb.center.x <= 300.0 {
-                if guy_aabb.center.y > 70.0 && guy_aabb.center.y < 150.0 {
-                    //bottom door collision
-                    move_to_level(game, 1);
-                } else if guy_aabb.center.y < 215.0 && guy_aabb.center.y > 165.0 {
-                    //top door collision
-                    move_to_level(game, 3);
-                }
-            }
+    game.door_tiles.clear();
+    match loadLevel(
+        &mut engine.assets,
+        &game.tiles,
+        &mut game.collision_objects,
+        &mut game.door_tiles,
+        game.level,
+    ) {
+        Ok(level) => {
+            game.level_data = level;
+            spawnDoors(game, engine);
         }
+        Err(err) => eprintln!("{}", err),
+    }
+    setUpLevel(game);
+}
 
-        // Starting Area - Bottom Door Open
-        1 => {
-            if engine.input.is_key_pressed(engine::Key::Space) {
-                game.sfx_manager.play(game.sfx[7].clone());
-                game.mode = GameMode::SimonSays;
-                render_platformer(game, engine);
-                //game.simon_says.completed = true; // Uncomment to skip game
-                return;
-            } else if guy_aabb.center.x < 250.0 {
-                //door close, guy left doorway
-                move_to_level(game, 0);
-            }
+// Play `level` in place of the current level's file, with the guy starting at
+// `spawn`; the editor playtests this way without saving first
+pub fn playLevel(game: &mut Game, engine: &mut Engine, level: Level, spawn: Vec2) {
+    game.collision_objects.clear();
+    game.door_tiles.clear();
+    game.level_data = Rc::new(level);
+    match levelTiles(&mut engine.assets, &game.level_data) {
+        Ok(rows) => placeTiles(
+            &game.tiles,
+            &rows,
+            &mut game.collision_objects,
+            &mut game.door_tiles,
+        ),
+        Err(err) => eprintln!("{}", err),
+    }
+    spawnDoors(game, engine);
+    setUpLevel(game);
+    game.guy.spawn_at(spawn);
+    panToGuy(game);
+}
 
-            if game.simon_says.completed {
-                game.simon_says.completed = false;
-                move_to_level(game, 5);
-            }
+// Everything that follows from the current level's tiles and data
+fn setUpLevel(game: &mut Game) {
+    indexWalls(game);
+    spawnEnemies(game);
+    spawnObjects(game);
+    game.follow_camera.bounds = levelBounds(game);
+}
+
+// The camera that follows the guy around levels bigger than the screen
+pub fn followCamera() -> FollowCamera {
+    let mut camera = FollowCamera::new(Vec2::new(W / 2.0, H / 2.0), Vec2::new(W, H));
+    camera.dead_zone = CAMERA_DEAD_ZONE;
+    camera.look_ahead = CAMERA_LOOK_AHEAD;
+    camera.smoothing = CAMERA_SMOOTHING;
+    camera
+}
+
+// The area covered by the current level's tiles
+pub fn levelBounds(game: &Game) -> Option<AABB> {
+    let mut tiles = game.collision_objects.iter().map(|tile| tile.collision);
+    let first = tiles.next()?;
+    let (mut min, mut max) = (
+        first.center - first.size / 2.0,
+        first.center + first.size / 2.0,
+    );
+    for tile in tiles {
+        min = min.min(tile.center - tile.size / 2.0);
+        max = max.max(tile.center + tile.size / 2.0);
+    }
+    Some(AABB {
+        center: (min + max) / 2.0,
+        size: max - min,
+    })
+}
+
+// Put every tile that can ever block the guy into the wall grid.
+// Doors stay in even when open, since they can lock again.
+pub fn indexWalls(game: &mut Game) {
+    game.wall_grid.clear();
+    for (wall_idx, wall) in game.collision_objects.iter().enumerate() {
+        if game.tiles.get(&wall.tex_coord).collision != Collision::None {
+            game.wall_grid.insert(wall_idx, wall.collision);
         }
+    }
+}
 
-        // Starting Area - Top Door Open
-        3 => {
-            // bottom door open
+// The tiles that are solid right now and touch `region`, and their indices in
+// collision_objects
+fn nearbyWalls(game: &Game, region: AABB) -> (Vec<usize>, Vec<AABB>) {
+    game.wall_grid
+        .query(region)
+        .into_iter()
+        .filter(|wall_idx| isSolid(game, *wall_idx))
+        .map(|wall_idx| (wall_idx, game.collision_objects[wall_idx].collision))
+        .unzip()
+}
 
-            if engine.input.is_key_pressed(engine::Key::Space) {
-                game.sfx_manager.play(game.sfx[7].clone());
-                game.mode = GameMode::ConnectWires;
-                render_platformer(game, engine);
-                //game.connect_wires.completed = true; // Uncomment to skip game
-                return;
-            } else if guy_aabb.center.x < 250.0 {
-                //door close, guy left doorway
-                move_to_level(game, 0);
+// The tiles a body moving by `motion` can run into, and their indices in
+// collision_objects. Slopes are left to landOnSlopes, and one-way platforms
+// only count if the body starts out on top of them and isn't `dropping` through.
+fn bodyWalls(game: &Game, body: AABB, motion: Vec2, dropping: bool) -> (Vec<usize>, Vec<AABB>) {
+    let reach = AABB {
+        center: body.center + motion / 2.0,
+        size: body.size + motion.abs(),
+    };
+    let feet = body.center.y - body.size.y / 2.0;
+    let (wall_idxs, walls) = nearbyWalls(game, reach);
+    wall_idxs
+        .into_iter()
+        .zip(walls)
+        .filter(|(wall_idx, wall)| {
+            let props = game.tiles.get(&game.collision_objects[*wall_idx].tex_coord);
+            if matches!(props.collision, Collision::Slope(..)) {
+                return false;
             }
+            let top = wall.center.y + wall.size.y / 2.0;
+            !props.one_way || (!dropping && feet >= top - ONE_WAY_TOLERANCE)
+        })
+        .unzip()
+}
 
-            if game.connect_wires.completed {
-                game.connect_wires.completed = false;
-                move_to_level(game, 2);
-            }
+// Stand `body` on the highest slope under it, if there's one it could have got
+// onto from where it was `before` moving. Bodies that were on the ground stay
+// on it going downhill. Returns true if it's standing on a slope.
+fn landOnSlopes(game: &Game, body: &mut AABB, before: AABB, vel: Vec2, was_grounded: bool) -> bool {
+    if vel.y > 0.0 {
+        return false;
+    }
+    let half = body.size / 2.0;
+    let feet = body.center.y - half.y;
+    let lowest = feet - if was_grounded { SLOPE_SNAP } else { 0.0 };
+    let highest = (before.center.y - half.y).max(feet + SLOPE_STEP);
+    let region = AABB {
+        center: Vec2::new(body.center.x, (lowest + highest) / 2.0),
+        size: Vec2::new(body.size.x, highest - lowest),
+    };
+    let mut floor: Option<f32> = None;
+    for wall_idx in game.wall_grid.query(region) {
+        let tile = &game.collision_objects[wall_idx];
+        let collision = game.tiles.get(&tile.tex_coord).collision;
+        let left = tile.collision.center.x - tile.collision.size.x / 2.0;
+        let bottom = tile.collision.center.y - tile.collision.size.y / 2.0;
+        // how far across the tile the body's edges are; the floor is highest
+        // at one or the other
+        let ends = [body.center.x - half.x, body.center.x + half.x]
+            .map(|x| (x - left) / tile.collision.size.x);
+        if ends[1] <= 0.0 || ends[0] >= 1.0 {
+            continue;
+        }
+        let Some(height) = ends
+            .iter()
+            .filter_map(|t| collision.floor_at(*t))
+            .reduce(f32::max)
+        else {
+            continue;
+        };
+        let y = bottom + height * tile.collision.size.y;
+        if (lowest..=highest).contains(&y) {
+            floor = Some(floor.map_or(y, |floor| floor.max(y)));
         }
+    }
+    let Some(y) = floor else {
+        return false;
+    };
+    body.center.y = y + half.y;
+    true
+}
 
-        // Top Area
-        2 => {
-            // Change respawn position based on position in level
-            if game.guy.pos.y > H / 2.0 && game.guy.respawn_pos == Vec2::new(W / 2.0, H / 4.0) {
-                // Where they spawn in
-                game.guy.set_respawn();
-            } else if game.guy.pos.y < (H / 4.0) && game.guy.pos.x > (W * 0.8) {
-                // Bottom right corner after first spike
-                game.guy.set_respawn();
-            } else if game.guy.pos.y < (H / 4.0) && game.guy.pos.x < (W / 3.0) {
-                // After both acid pits
-                game.guy.set_respawn();
-            }
+// The solid tiles near the line from `start` to `end`
+fn wallsAlong(game: &Game, start: Vec2, end: Vec2) -> Vec<AABB> {
+    game.wall_grid
+        .query_segment(start, end)
+        .into_iter()
+        .filter(|wall_idx| isSolid(game, *wall_idx))
+        .map(|wall_idx| game.collision_objects[wall_idx].collision)
+        .collect()
+}
 
-            if guy_aabb.center.x > 95.0
-                && guy_aabb.center.x <= 125.0
-                && guy_aabb.center.y > 165.0
-                && guy_aabb.center.y < 250.0
-            {
-                //door collision
-                move_to_level(game, 4);
-            }
+fn isSolid(game: &Game, wall_idx: usize) -> bool {
+    // door tiles can be walked through unless the door is locked
+    !game.door_tiles.contains(&wall_idx)
+        || game
+            .doors
+            .iter()
+            .any(|door| door.is_solid() && door.tiles.contains(&wall_idx))
+}
 
-            // Restart from beginning
-            if engine.input.is_key_pressed(engine::Key::Escape) {
-                game.guy = Guy {
-                    pos: Vec2 {
-                        x: W / 2.0,
-                        y: H / 4.0,
-                    },
-                    vel: Vec2 { x: 0.0, y: 0.0 },
-                    grounded: false,
-                    frame: 0,
-                    respawn_pos: Vec2 {
-                        x: W / 2.0,
-                        y: H / 4.0,
-                    },
-                };
-                move_to_level(game, 0);
-            }
+// The solid objects (platforms and boxes) touching `region`, other than
+// `except`, and their indices in game.objects
+fn nearbyObjects(game: &Game, region: AABB, except: Option<usize>) -> (Vec<usize>, Vec<AABB>) {
+    game.objects
+        .iter()
+        .enumerate()
+        .filter(|(i, object)| Some(*i) != except && object.is_solid())
+        .map(|(i, object)| (i, object.aabb()))
+        .filter(|(_, aabb)| region.contact(*aabb).is_some())
+        .unzip()
+}
+
+// Put the current level's objects back where they started
+pub fn spawnObjects(game: &mut Game) {
+    game.objects = game
+        .level_data
+        .objects
+        .iter()
+        .map(|def| Object::new(def.clone()))
+        .collect();
+    game.riding = None;
+}
+
+// Put the current level's enemies back where they started
+pub fn spawnEnemies(game: &mut Game) {
+    game.enemies = game
+        .level_data
+        .enemies
+        .iter()
+        .map(|def| {
+            let sheet = game.enemy_anims[enemy::kindIndex(def.kind)].clone();
+            Enemy::new(def.clone(), sheet)
+        })
+        .collect();
+    game.shots.clear();
+}
+
+// Start over from the beginning with full lives and every minigame unsolved
+pub fn newGame(game: &mut Game, engine: &mut Engine) {
+    game.solved.clear();
+    game.simon_says = simonsays::initialize(&mut engine.rng);
+    game.connect_wires = connectwires::initialize();
+    game.mining = mining::initialize(&mut engine.rng);
+    game.guy.lives = GUY_LIVES;
+    take_exit(game, engine, 0, Some("start"));
+}
+
+// The guy goes back to his respawn point, and the enemies back to their posts
+fn respawn(game: &mut Game) {
+    game.guy.die();
+    spawnEnemies(game);
+    panToGuy(game);
+}
+
+// Ease the camera over to wherever the guy is now from whatever was on screen,
+// zooming back out on the way
+fn panToGuy(game: &mut Game) {
+    let shown = game.camera_effects.apply(game.follow_camera.camera());
+    game.follow_camera.snap_to(game.guy.pos);
+    game.camera_effects.pan_from(shown, CAMERA_PAN_FRAMES);
+    game.camera_effects.zoom_to(1.0, CAMERA_PAN_FRAMES);
+}
+
+// Set up the current level's doors over the door tiles in its grid
+pub fn spawnDoors(game: &mut Game, engine: &mut Engine) {
+    game.doors.clear();
+    let rows = match levelTiles(&mut engine.assets, &game.level_data) {
+        Ok(rows) => rows,
+        Err(err) => {
+            eprintln!("{}", err);
+            return;
         }
+    };
+    // index of the first tile of each row in collision_objects
+    let mut row_starts = Vec::with_capacity(rows.len());
+    let mut start = 0;
+    for row in rows.iter() {
+        row_starts.push(start);
+        start += row.len();
+    }
+    let tile_index = |(col, row): (usize, usize)| {
+        rows.get(row)
+            .filter(|tiles| col < tiles.len())
+            .map(|_| row_starts[row] + col)
+    };
 
-        // Top Area - Door Open
-        4 => {
-            // Top door room door open
-            if engine.input.is_key_pressed(engine::Key::Space) {
-                game.sfx_manager.play(game.sfx[7].clone());
-                move_to_level(game, 7);
-            } else if guy_aabb.center.x < 95.0 {
-                //door close, guy left doorway
-                move_to_level(game, 2);
-            }
+    for def in game.level_data.doors.iter() {
+        let (col, row) = def.at;
+        let (Some(bottom), Some(top)) = (tile_index((col, row)), tile_index((col, row + 1))) else {
+            eprintln!("door {:?} at {:?} is outside the level", def.name, def.at);
+            continue;
+        };
+        let mut door = Door::new(def.clone(), [bottom, top]);
+        door.reset(
+            |minigame| game.solved.contains(&minigame),
+            &mut game.collision_objects,
+        );
+        game.doors.push(door);
+    }
+}
+
+// Flip whatever objects and doors are called `name`
+fn toggleNamed(game: &mut Game, name: &str) {
+    for object in game
+        .objects
+        .iter_mut()
+        .filter(|object| object.def.name == name)
+    {
+        object.toggle();
+    }
+    for door in game.doors.iter_mut().filter(|door| door.def.name == name) {
+        door.toggle_lock(&mut game.collision_objects);
+    }
+}
+
+fn play_minigame(game: &Game, engine: &mut Engine, minigame: Minigame) -> Transition<Game> {
+    match minigame {
+        Minigame::SimonSays => Transition::Push(Box::new(SimonSaysScene::new(game, engine))),
+        Minigame::ConnectWires => Transition::Push(Box::new(ConnectWiresScene::new(game, engine))),
+        Minigame::Mining => Transition::Push(Box::new(MiningScene::new(game, engine))),
+    }
+}
+
+// Open doors as the guy walks up to them and close them behind him. Returns
+// what the door he pressed space at wants to do, if anything.
+fn update_doors(game: &mut Game, engine: &Engine) -> Option<(DoorAction, Option<usize>)> {
+    let pos = game.guy.pos;
+    let interact = engine.input.is_key_pressed(engine::Key::Space);
+    let has_requirement = |minigame| game.solved.contains(&minigame);
+    let mut action = None;
+    for door in game.doors.iter_mut() {
+        let near = door.def.region.contains(pos);
+        door.update(near, has_requirement, &mut game.collision_objects);
+        if near && interact && action.is_none() {
+            action = door
+                .try_open(has_requirement, &mut game.collision_objects)
+                .map(|door_action| (door_action, door.def.sfx));
         }
+    }
+    action
+}
 
-        // Bottom Area
-        5 => {
-            // Change respawn position based on position in level
-            if game.guy.pos.x > W * 0.8 && game.guy.respawn_pos == Vec2::new(W / 2.0, H / 4.0) {
-                // Where they spawn in
-                game.guy.set_respawn();
-            }
+// Move to a level, putting the guy at one of its spawn points if given
+pub fn take_exit(game: &mut Game, engine: &mut Engine, to: u16, spawn: Option<&str>) {
+    move_to_level(game, engine, to);
+    if let Some(name) = spawn {
+        match game.level_data.spawn(name) {
+            Some(pos) => game.guy.spawn_at(pos),
+            None => eprintln!("level {} has no spawn point {:?}", to, name),
+        }
+    }
+    panToGuy(game);
+}
 
-            if guy_aabb.center.x > 250.0
-                && guy_aabb.center.x <= 305.0
-                && guy_aabb.center.y > 160.0
-                && guy_aabb.center.y < 250.0
-            {
-                //door collision
-                move_to_level(game, 6);
-            }
+// Whether a trigger or exit should fire this frame
+fn fires(game: &mut Game, engine: &Engine, region: Option<Region>, when: When) -> bool {
+    let pos = game.guy.pos;
+    let inside = region.is_none_or(|region| region.contains(pos));
+    match when {
+        When::Enter => inside,
+        When::Leave => !inside,
+        When::Interact => inside && engine.input.is_key_pressed(engine::Key::Space),
+        When::Completed(minigame) => {
+            let completed = match minigame {
+                Minigame::SimonSays => &mut game.simon_says.completed,
+                Minigame::ConnectWires => &mut game.connect_wires.completed,
+                Minigame::Mining => &mut game.mining.completed,
+            };
+            std::mem::replace(completed, false)
+        }
+    }
+}
 
-            // Restart from beginning
-            if engine.input.is_key_pressed(engine::Key::Escape) {
-                game.guy = Guy {
-                    pos: Vec2 {
-                        x: W / 2.0,
-                        y: H / 4.0,
-                    },
-                    vel: Vec2 { x: 0.0, y: 0.0 },
-                    grounded: false,
-                    frame: 0,
-                    respawn_pos: Vec2 {
-                        x: W / 2.0,
-                        y: H / 4.0,
-                    },
-                };
-                move_to_level(game, 0);
-            }
+fn level_handling(game: &mut Game, engine: &mut Engine) -> Transition<Game> {
+    // everything that happens in a level comes from its level file
+    let level = game.level_data.clone();
+
+    // remember which minigames have ever been beaten, for locked doors
+    for (minigame, completed) in [
+        (Minigame::SimonSays, game.simon_says.completed),
+        (Minigame::ConnectWires, game.connect_wires.completed),
+        (Minigame::Mining, game.mining.completed),
+    ] {
+        if completed && !game.solved.contains(&minigame) {
+            game.solved.push(minigame);
         }
+    }
 
-        6 => {
-            // Bot door room door open
-            if engine.input.is_key_pressed(engine::Key::Space) {
-                game.sfx_manager.play(game.sfx[7].clone());
-                move_to_level(game, 7);
-            } else if guy_aabb.center.x < 250.0 || guy_aabb.center.y < 150.0 {
-                //door close, guy left doorway
-                move_to_level(game, 5);
+    if let Some((action, sfx)) = update_doors(game, engine) {
+        if let Some(sfx) = sfx {
+            playSfx(game, sfx);
+        }
+        match action {
+            DoorAction::Play(minigame) => return play_minigame(game, engine, minigame),
+            DoorAction::GoTo(to, spawn) => {
+                take_exit(game, engine, to, spawn.as_deref());
+                return Transition::None;
             }
         }
+    }
 
-        7 => {
-            // Final Area
-            // Change respawn position based on position in level
-            if game.guy.pos.y < H / 4.0 && game.guy.pos.x > (2.0 * W / 3.0) {
-                // Where they spawn in
-                game.guy.set_respawn();
-            }
+    for checkpoint in level.checkpoints.iter() {
+        if checkpoint.contains(game.guy.pos) {
+            game.guy.set_respawn();
+        }
+    }
 
-            if game.guy.pos.x < 65.0 && game.guy.pos.y > 30.0 && game.guy.pos.y < 90.0 {
-                move_to_level(game, 8);
+    for trigger in level.triggers.iter() {
+        if fires(game, engine, trigger.region, trigger.when) {
+            if let Some(sfx) = trigger.sfx {
+                playSfx(game, sfx);
             }
+            return play_minigame(game, engine, trigger.play);
         }
+    }
 
-        8 => {
-            // Final Area - Door Open
-            if engine.input.is_key_pressed(engine::Key::Space) {
-                game.sfx_manager.play(game.sfx[7].clone());
-                game.mining = mining::initialize();
-                game.mode = GameMode::Mining;
-                render_platformer(game, engine);
-                //game.connect_wires.completed = true; // Uncomment to skip game
-                return;
-            } else if game.guy.pos.x > 65.0 {
-                //door close, guy left doorway
-                move_to_level(game, 7);
+    for exit in level.exits.iter() {
+        if fires(game, engine, exit.region, exit.when) {
+            if let Some(sfx) = exit.sfx {
+                playSfx(game, sfx);
             }
+            take_exit(game, engine, exit.to, exit.spawn.as_deref());
+            break;
+        }
+    }
+    Transition::None
+}
 
-            if game.mining.completed {
-                game.mining.completed = false;
-                game.guy = Guy {
-                    pos: Vec2 {
-                        x: W / 2.0,
-                        y: H / 4.0,
-                    },
-                    vel: Vec2 { x: 0.0, y: 0.0 },
-                    grounded: false,
-                    frame: 0,
-                    respawn_pos: Vec2 {
-                        x: W / 2.0,
-                        y: H / 4.0,
-                    },
-                };
-                game.sfx_manager.play(game.sfx[7].clone());
-                move_to_level(game, 0);
-                move_to_level(game, 0);
-            }
-            // if engine.input.is_key_pressed(engine::Key::Space) {
-            //     //TODO write final game here
+pub struct PlatformerSprites {
+    bg: Sprite,
+    walls: SpriteRange,
+    // one per guy sprite group, only the one with his current frame showing
+    guy: Vec<Sprite>,
+    // HUD: a little guy and the lives left, then a heart (well, an apple) per health
+    lives_icon: Sprite,
+    lives: SpriteRange,
+    health: SpriteRange,
+    font: BitFont,
+    // per enemy kind, one range per texture its clips draw from
+    enemies: Vec<Vec<SpriteRange>>,
+    // per enemy kind, its shots
+    shots: Vec<SpriteRange>,
+    // moving platforms, drawn from the tile sheet
+    platforms: SpriteRange,
+    // one range per object texture, in objects::OBJECT_TEXTURES order
+    objects: Vec<SpriteRange>,
+}
 
-            //
-            // }else if game.guy.pos.x > 65.0 {
-            //     move_to_level(game, 7);
-            // }
-        }
+pub struct PlatformerScene {
+    sprites: PlatformerSprites,
+    groups: Vec<SpriteGroup>,
+}
 
-        _ => (),
+impl PlatformerScene {
+    pub fn new(game: &Game, engine: &mut Engine) -> Self {
+        let (guy, tiles) = (&game.sprite_groups.guy, game.sprite_groups.tiles);
+        let sprites = PlatformerSprites {
+            bg: engine.renderer.alloc_sprite(tiles),
+            walls: engine.renderer.alloc_sprites(tiles, 0), // sized to the level when rendering
+            guy: guy
+                .iter()
+                .map(|group| engine.renderer.alloc_sprite(*group))
+                .collect(),
+            lives_icon: engine.renderer.alloc_sprite(guy[0]),
+            lives: engine.renderer.alloc_sprites(game.sprite_groups.hud, 2),
+            health: engine
+                .renderer
+                .alloc_sprites(game.sprite_groups.hud, GUY_MAX_HEALTH as usize),
+            font: hudFont(game.sprite_groups.hud),
+            // sized when rendering
+            enemies: game
+                .sprite_groups
+                .enemies
+                .iter()
+                .map(|groups| {
+                    groups
+                        .iter()
+                        .map(|group| engine.renderer.alloc_sprites(*group, 0))
+                        .collect()
+                })
+                .collect(),
+            shots: game
+                .sprite_groups
+                .shots
+                .iter()
+                .map(|group| engine.renderer.alloc_sprites(*group, 0))
+                .collect(),
+            platforms: engine.renderer.alloc_sprites(tiles, 0),
+            objects: game
+                .sprite_groups
+                .objects
+                .iter()
+                .map(|group| engine.renderer.alloc_sprites(*group, 0))
+                .collect(),
+        };
+        let mut groups = vec![guy[0], tiles];
+        groups.extend(game.sprite_groups.enemies.iter().flatten());
+        groups.extend(&game.sprite_groups.shots);
+        groups.extend(&guy[1..]);
+        groups.extend(&game.sprite_groups.objects);
+        groups.push(game.sprite_groups.hud);
+        Self { sprites, groups }
+    }
+}
+
+impl Scene<Game> for PlatformerScene {
+    fn update(&mut self, game: &mut Game, engine: &mut Engine) -> Transition<Game> {
+        update_platformer(game, engine)
+    }
+
+    fn render(&mut self, game: &mut Game, engine: &mut Engine) {
+        render_platformer(game, engine, &mut self.sprites)
+    }
+
+    fn sprite_groups(&self) -> &[SpriteGroup] {
+        &self.groups
+    }
+}
+
+fn levelChanged(game: &Game, engine: &mut Engine) -> bool {
+    // take both, so an edit to each doesn't reload the level twice
+    let level = engine.assets.take_changed(levelPath(game.level));
+    let tiles = match &game.level_data.tiles {
+        Tiles::Import(file) => engine.assets.take_changed(format!("Levels/{}", file)),
+        Tiles::Grid(_) => false,
+    };
+    level || tiles
+}
+
+// Pick up edits to the guy's or the enemies' animation files
+// Sprite groups are made for each animation texture when the game starts, so a
+// reloaded sheet has to keep drawing from the same textures
+fn sameTextures(old: &AnimationSheet, new: &AnimationSheet) -> bool {
+    old.textures.len() == new.textures.len()
+        && old
+            .textures
+            .iter()
+            .zip(new.textures.iter())
+            .all(|(old, new)| Rc::ptr_eq(old, new))
+}
+
+fn texturesChanged(path: &str) {
+    eprintln!(
+        "{}: the textures it draws from can't change while the game is running, so keeping the old clips; restart to use the new ones",
+        path
+    );
+}
+
+fn reloadAnimations(game: &mut Game, engine: &mut Engine) {
+    if engine.assets.take_changed(GUY_ANIMATIONS) {
+        match engine
+            .assets
+            .animation(&mut engine.renderer, GUY_ANIMATIONS)
+        {
+            Ok(sheet) if !sameTextures(game.guy.anim.sheet(), &sheet) => {
+                texturesChanged(GUY_ANIMATIONS)
+            }
+            Ok(sheet) => game.guy.anim.set_sheet(sheet),
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+    for (i, kind) in enemy::KINDS.into_iter().enumerate() {
+        if !engine.assets.take_changed(enemy::animationPath(kind)) {
+            continue;
+        }
+        match engine
+            .assets
+            .animation(&mut engine.renderer, enemy::animationPath(kind))
+        {
+            Ok(sheet) if !sameTextures(&game.enemy_anims[i], &sheet) => {
+                texturesChanged(&enemy::animationPath(kind))
+            }
+            Ok(sheet) => {
+                for enemy in game
+                    .enemies
+                    .iter_mut()
+                    .filter(|enemy| enemy.def.kind == kind)
+                {
+                    enemy.anim.set_sheet(sheet.clone());
+                }
+                game.enemy_anims[i] = sheet;
+            }
+            Err(err) => eprintln!("{}", err),
+        }
     }
 }
 
-pub fn update_platformer(game: &mut Game, engine: &mut Engine) {
+pub fn update_platformer(game: &mut Game, engine: &mut Engine) -> Transition<Game> {
+    let (was_dead, health_before) = (game.guy.is_dead(), game.guy.health);
     // Character movement ------------------------------------------------------------------------
     let dir_x = engine.input.key_axis(engine::Key::Left, engine::Key::Right);
-    let dir_y = engine.input.key_axis(engine::Key::Down, engine::Key::Up);
-    //println!("dirx: {}, diry: {}", dir_x, dir_y);
+    //println!("dirx: {}", dir_x);
     println!("level num: {}", game.level);
-    game.guy.moveGuy(dir_x, dir_y);
+    // pick up edits to the movement settings, level file or tileset while the game is running
+    if engine.assets.take_changed(movement::MOVEMENT_PATH) {
+        match engine.assets.load(movement::MOVEMENT_PATH, Movement::load) {
+            Ok(movement) => game.movement = movement,
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+    let tileset_changed = engine.assets.take_changed(tiles::TILESET_PATH);
+    if tileset_changed {
+        match engine.assets.load(tiles::TILESET_PATH, Tileset::load) {
+            Ok(tiles) => game.tiles = tiles,
+            Err(err) => eprintln!("{}", err),
+        }
+    }
+    if levelChanged(game, engine) || tileset_changed {
+        move_to_level(game, engine, game.level);
+    }
+    reloadAnimations(game, engine);
+    if engine.input.is_key_pressed(engine::Key::X) {
+        game.guy.attack();
+    }
+    let jump_pressed = engine.input.is_key_pressed(engine::Key::Up);
+    let jump_held = engine.input.is_key_down(engine::Key::Up);
+    let movement = *game.movement;
+    let jumped = game.guy.moveGuy(dir_x, jump_pressed, jump_held, &movement);
 
     //Play jump sound
-    if !game.guy.grounded && game.guy.vel.y == 9.0 {
+    if jumped {
         //println!("playing sound");
-        game.sfx_manager.play(game.sfx[1].clone()).unwrap();
+        playSfx(game, 1);
     }
     // Character movement ------------------------------------------------------------------------
 
     if engine.input.is_key_pressed(engine::Key::R) {
-        game.guy.die();
+        respawn(game);
     }
 
     if engine.input.is_key_pressed(engine::Key::L) {
-        game.level = 0;
-        loadLevel(&mut game.collision_objects, &mut game.doors, 0);
+        move_to_level(game, engine, 0);
         game.guy.die();
     }
 
+    // Restart from beginning
+    if engine.input.is_key_pressed(engine::Key::Escape) {
+        take_exit(game, engine, 0, Some("start"));
+    }
+
     if engine.input.is_key_pressed(engine::Key::S) {
-        game.mode = GameMode::SimonSays;
-        if !matches!(game.mode, GameMode::Platformer) {
-            render_platformer(game, engine);
-        }
+        return Transition::Push(Box::new(SimonSaysScene::new(game, engine)));
     }
 
-    let guy_aabb = AABB {
+    if engine.input.is_key_pressed(engine::Key::P) {
+        return Transition::Push(Box::new(PauseScene));
+    }
+
+    if engine.input.is_key_pressed(engine::Key::E) {
+        return Transition::Push(Box::new(EditorScene::new(game, engine)));
+    }
+
+    let transition = level_handling(game, engine);
+    if !matches!(transition, Transition::None) {
+        return transition;
+    }
+
+    // Objects ------------------------------------------------------------------------
+    update_objects(game, engine);
+    let mut guy_aabb = AABB {
         center: game.guy.pos,
         size: Vec2 { x: 16.0, y: 16.0 },
     };
-
-    level_handling(game, engine, &guy_aabb);
+    pushBoxes(game, guy_aabb);
+    // Objects ------------------------------------------------------------------------
 
     // Collision ------------------------------------------------------------------------
-    let mut contacts = Vec::with_capacity(game.collision_objects.len());
-    // TODO: for multiple guys this might be better as flags on the guy for what side he's currently colliding with stuff on
-    for _iter in 0..COLLISION_STEPS {
-        contacts.clear();
-
-        // TODO: to generalize to multiple guys, need to iterate over guys first and have guy_index, rect_index, displacement in a contact tuple
-
-        contacts.extend(
-            game.collision_objects
-                .iter()
-                .enumerate()
-                .filter_map(|(ri, w)| w.collision.displacement(guy_aabb).map(|d| (ri, d))),
-        );
-
-        if contacts.is_empty() {
-            break;
-        }
-        // This part stays mostly the same for multiple guys, except the shape of contacts is different
-        contacts.sort_by(|(_r1i, d1), (_r2i, d2)| {
-            d2.length_squared()
-                .partial_cmp(&d1.length_squared())
-                .unwrap()
-        });
-        let x_tex_region = (guy_aabb.center.x); //TILE_SIZE as f32) as u16;
-                                                //println!("{}, {}", x_tex_region, guy_aabb.center.y);
-                                                // check if guy collides with doors
-        if guy_aabb.center.x > 250.0 && guy_aabb.center.x <= 300.0 && game.level == 0 {
-            if guy_aabb.center.y > 70.0 && guy_aabb.center.y < 150.0 {
-                //bottom door collision
-                game.level = 1;
-                game.collision_objects.clear();
-                loadLevel(&mut game.collision_objects, &mut game.doors, 1);
-            } else if guy_aabb.center.y < 215.0 && guy_aabb.center.y > 165.0 {
-                //top door collision
-                game.level = 3;
-                game.collision_objects.clear();
-                loadLevel(&mut game.collision_objects, &mut game.doors, 3);
+    // Everything solid near where the guy could get to this frame, with its index
+    // in collision_objects
+    // TODO: for multiple guys this should run once per guy
+    let reach = AABB {
+        center: guy_aabb.center + game.guy.vel / 2.0,
+        size: guy_aabb.size + game.guy.vel.abs(),
+    };
+    // platforms and boxes go on the end, after the tiles
+    // holding down drops through one-way platforms
+    let dropping = engine.input.is_key_down(engine::Key::Down);
+    let (wall_idxs, mut walls) = bodyWalls(game, guy_aabb, game.guy.vel, dropping);
+    let (object_idxs, object_walls) = nearbyObjects(game, reach, None);
+    walls.extend(object_walls);
+
+    // sweep along the velocity so falling fast can't skip over a thin platform,
+    // then push out of anything we were already stuck in (after a respawn, say)
+    let before = guy_aabb;
+    let hits = move_and_slide(&mut guy_aabb, game.guy.vel, &walls);
+    let contacts = resolve_contacts(&mut guy_aabb, &walls);
+    let on_slope = landOnSlopes(game, &mut guy_aabb, before, game.guy.vel, game.guy.grounded);
+    game.guy.pos = guy_aabb.center;
+
+    // the most harmful hazard touched, and where it is
+    let mut hit_hazard: Option<(Hazard, Vec2)> = None;
+    game.guy.grounded = false;
+    game.riding = None;
+    let touched = hits
+        .iter()
+        .map(|hit| (hit.wall, hit.normal))
+        .chain(contacts.iter().map(|(i, contact)| (*i, contact.normal)));
+    for (i, normal) in touched {
+        let object = i.checked_sub(wall_idxs.len()).map(|j| object_idxs[j]);
+        if object.is_none() {
+            let wall = &game.collision_objects[wall_idxs[i]];
+            if let Some(hazard) = game.tiles.get(&wall.tex_coord).hazard {
+                if hit_hazard.is_none_or(|(worst, _)| hazard.damage > worst.damage) {
+                    hit_hazard = Some((hazard, wall.collision.center));
+                }
             }
         }
 
-        if game.level == 1 {
-            if engine.input.is_key_pressed(engine::Key::Space) {
-                game.mode = GameMode::SimonSays;
-                render_platformer(game, engine);
-                return;
-            } else if guy_aabb.center.x < 250.0 {
-                //door close, guy left doorway
-                game.level = 0;
-                game.collision_objects.clear();
-                loadLevel(&mut game.collision_objects, &mut game.doors, 0);
+        if normal.y != 0.0 {
+            // landed on something or bumped our head
+            game.guy.vel.y = 0.0;
+            if normal.y > 0.0 {
+                game.guy.grounded = true;
+                // standing on a platform or box rides along with it
+                if object.is_some() {
+                    game.riding = object;
+                }
             }
+        } else {
+            game.guy.vel.x = 0.0;
         }
+    }
+    if on_slope {
+        game.guy.vel.y = 0.0;
+        game.guy.grounded = true;
+    }
 
-        if game.level == 3 {
-            // top door open
-            if engine.input.is_key_pressed(engine::Key::Space) {
-                game.mode = GameMode::ConnectWires;
-                render_platformer(game, engine);
-                return;
-                // game.level = 2;
-                // game.collision_objects.clear();
-                // loadLevel(&mut game.collision_objects, &mut game.doors, 2);
-            } else if guy_aabb.center.x < 250.0 {
-                //door close, guy left doorway
-                game.level = 0;
-                game.collision_objects.clear();
-                loadLevel(&mut game.collision_objects, &mut game.doors, 0);
-            }
+    if let Some((hazard, from)) = hit_hazard {
+        if game.guy.hurt(from, hazard.damage) {
+            playSfx(game, 2);
         }
-
-        if game.level == 2 {
-            // mini game 1
-            if engine.input.is_key_pressed(engine::Key::Escape) {
-                game.level = 0;
-                game.guy = Guy {
-                    pos: Vec2 {
-                        x: W / 2.0,
-                        y: H / 2.0,
-                    },
-                    vel: Vec2 { x: 0.0, y: 0.0 },
-                    grounded: false,
-                    frame: 0,
-                    respawn_pos: Vec2 {
-                        x: W / 2.0,
-                        y: H / 2.0,
-                    },
-                };
-                game.collision_objects.clear();
-                loadLevel(&mut game.collision_objects, &mut game.doors, 0);
-            }
+    }
+    let guy_shape = Shape::from(guy_aabb);
+    let saw_hit = game
+        .objects
+        .iter()
+        .filter_map(|object| object.saw())
+        .find(|saw| Shape::from(*saw).overlaps(&guy_shape));
+    if let Some(saw) = saw_hit {
+        if game.guy.hurt(saw.center, objects::SAW_DAMAGE) {
+            playSfx(game, 2);
         }
+    }
+    // Collision ------------------------------------------------------------------------
 
-        for (wall_idx, _disp) in contacts.iter() {
-            if !game.doors.contains(&(*wall_idx as u16)) {
-                if game.collision_objects[*wall_idx].tex_coord.0 == NO_COLLISION {
-                    continue;
-                }
-
-                if DEATH_COLLISION.contains(&game.collision_objects[*wall_idx].tex_coord) {
-                    game.sfx_manager.play(game.sfx[2].clone());
-                    game.guy.die();
-                }
-
-                // TODO: for multiple guys should access game.guys[guy_idx].
-                let guy_aabb = AABB {
-                    center: game.guy.pos,
-                    size: Vec2 { x: 16.0, y: 16.0 },
-                };
-                let wall = game.collision_objects[*wall_idx].collision;
-
-                let mut disp = wall.displacement(guy_aabb).unwrap_or(Vec2::ZERO);
-
-                // We got to a basically zero collision amount
-                if disp.x.abs() < std::f32::EPSILON || disp.y.abs() < std::f32::EPSILON {
-                    break;
-                }
-
-                // Guy is below wall, push down
-                if game.guy.pos.y < wall.center.y {
-                    disp.y *= -1.0;
-                } else if game.guy.pos.y > wall.center.y {
-                    disp.y *= 1.0;
-                }
+    // Enemies ------------------------------------------------------------------------
+    if update_enemies(game, guy_aabb) {
+        playSfx(game, 2);
+    }
+    // Enemies ------------------------------------------------------------------------
+
+    // Camera ------------------------------------------------------------------------
+    if game.guy.is_dead() && !was_dead {
+        game.camera_effects.add_trauma(DEATH_TRAUMA);
+        game.camera_effects.zoom_to(DEATH_ZOOM, DEATH_ZOOM_FRAMES);
+    } else if game.guy.health < health_before {
+        game.camera_effects.add_trauma(HURT_TRAUMA);
+    }
+    game.follow_camera.update(game.guy.pos, game.guy.vel);
+    game.camera_effects.update();
 
-                // Guy is left of wall, push left
-                if game.guy.pos.x < wall.center.x {
-                    disp.x *= -1.0;
+    if game.guy.death_finished() {
+        game.guy.lives = game.guy.lives.saturating_sub(1);
+        if game.guy.lives == 0 {
+            return Transition::Push(Box::new(GameOverScene::new(game, engine)));
+        }
+        respawn(game);
+    }
+    Transition::None
+}
 
-                // Guy is right of wall, push left
-                } else if game.guy.pos.x > wall.center.x {
-                    disp.x *= 1.0;
-                }
+// Flip any switch the guy presses space at, move the platforms and saws along
+// their paths and let boxes fall, then carry the guy along with whatever he's
+// standing on
+fn update_objects(game: &mut Game, engine: &Engine) {
+    if engine.input.is_key_pressed(engine::Key::Space) {
+        let guy = game.guy.pos;
+        let mut flipped = Vec::default();
+        for object in game.objects.iter_mut() {
+            if !object.targets().is_empty() && object.aabb().contains(guy.x, guy.y) {
+                object.toggle();
+                flipped.extend(object.targets().iter().cloned());
+            }
+        }
+        for name in flipped {
+            toggleNamed(game, &name);
+        }
+    }
 
-                if disp.y.abs() <= disp.x.abs() {
-                    // Guy is above wall, push up
+    for object in game.objects.iter_mut() {
+        object.travel();
+    }
 
-                    game.guy.pos.y += disp.y;
-                    game.guy.vel.y = 0.0;
+    for i in 0..game.objects.len() {
+        if !game.objects[i].is_box() {
+            continue;
+        }
+        let object = &mut game.objects[i];
+        object.doGravity();
+        let mut aabb = object.aabb();
+        let start = aabb.center;
+        let motion = object.vel;
+        let reach = AABB {
+            center: aabb.center + motion / 2.0,
+            size: aabb.size + motion.abs(),
+        };
+        let (_, mut walls) = bodyWalls(game, aabb, motion, false);
+        walls.extend(nearbyObjects(game, reach, Some(i)).1);
+        let before = aabb;
+        let hits = move_and_slide(&mut aabb, motion, &walls);
+        resolve_contacts(&mut aabb, &walls);
+        let on_slope = landOnSlopes(game, &mut aabb, before, motion, false);
+        let object = &mut game.objects[i];
+        object.pos = aabb.center;
+        object.moved = aabb.center - start;
+        if on_slope || hits.iter().any(|hit| hit.normal.y != 0.0) {
+            object.vel.y = 0.0;
+        }
+    }
 
-                    if game.guy.vel.y <= 0.0 && disp.y > 0.0 {
-                        game.guy.grounded = true;
-                    }
+    if let Some(i) = game.riding {
+        let moved = game.objects[i].moved;
+        let mut guy_aabb = AABB {
+            center: game.guy.pos,
+            size: Vec2 { x: 16.0, y: 16.0 },
+        };
+        let (_, walls) = bodyWalls(game, guy_aabb, moved, false);
+        move_and_slide(&mut guy_aabb, moved, &walls);
+        game.guy.pos = guy_aabb.center;
+    }
+}
 
-                    // so far it seems resolved; for multiple guys this should probably set a flag on the guy
-                } else if disp.x.abs() <= disp.y.abs() {
-                    game.guy.pos.x += disp.x;
-                    game.guy.vel.x = 0.0;
+// Shove along any box the guy is walking into from the side, as far as it'll go
+fn pushBoxes(game: &mut Game, guy_aabb: AABB) {
+    let push = Vec2::new(game.guy.vel.x, 0.0);
+    if push.x == 0.0 {
+        return;
+    }
+    let ahead = AABB {
+        center: guy_aabb.center + push,
+        size: guy_aabb.size,
+    };
+    let feet = guy_aabb.center.y - guy_aabb.size.y / 2.0;
+    for i in 0..game.objects.len() {
+        let object = &game.objects[i];
+        let mut aabb = object.aabb();
+        let beside = feet < aabb.center.y + aabb.size.y / 2.0 - 1.0;
+        let in_front = (aabb.center.x - guy_aabb.center.x) * push.x > 0.0;
+        if !object.is_box() || !beside || !in_front || ahead.contact(aabb).is_none() {
+            continue;
+        }
+        let reach = AABB {
+            center: aabb.center + push / 2.0,
+            size: aabb.size + push.abs(),
+        };
+        let (_, mut walls) = bodyWalls(game, aabb, push, false);
+        walls.extend(nearbyObjects(game, reach, Some(i)).1);
+        move_and_slide(&mut aabb, push, &walls);
+        game.objects[i].pos = aabb.center;
+    }
+}
 
-                    // so far it seems resolved; for multiple guys this should probably set a flag on the guy
-                }
+// Moves the enemies and their shots. Returns true if one of them hurt the guy.
+fn update_enemies(game: &mut Game, guy_aabb: AABB) -> bool {
+    let mut guy_hit = false;
+    let attack_box = game.guy.attack_box();
+    for i in 0..game.enemies.len() {
+        let enemy = &game.enemies[i];
+        let aabb = enemy.aabb();
+
+        // look for the guy, and for anything in the way of walking on
+        let to_guy = game.guy.pos - enemy.eye();
+        let in_front = to_guy.x * enemy.facing >= 0.0 || enemy.is_chasing();
+        let sees_guy = enemy.is_alive()
+            && in_front
+            && to_guy.length() <= enemy.sight()
+            && line_of_sight(
+                enemy.eye(),
+                game.guy.pos,
+                &wallsAlong(game, enemy.eye(), game.guy.pos),
+            );
+        let front = enemy.pos.x + enemy.facing * (aabb.size.x / 2.0 + 1.0);
+        // slopes and one-way platforms can be walked onto, so they don't count
+        let probe = AABB::new(front, enemy.pos.y, 2.0, aabb.size.y - 2.0);
+        let wall_ahead = !bodyWalls(game, probe, Vec2::ZERO, false).1.is_empty();
+        // hazards don't count as something to stand on
+        let (floor_idxs, _) = nearbyWalls(
+            game,
+            AABB::new(front, enemy.pos.y - aabb.size.y / 2.0 - 4.0, 2.0, 4.0),
+        );
+        let floor_ahead = floor_idxs.iter().any(|idx| {
+            game.tiles
+                .get(&game.collision_objects[*idx].tex_coord)
+                .hazard
+                .is_none()
+        });
+        let senses = Senses {
+            guy: game.guy.pos,
+            sees_guy,
+            blocked: wall_ahead || !floor_ahead,
+        };
+
+        let enemy = &mut game.enemies[i];
+        if let Some(shot) = enemy.think(&senses) {
+            game.shots.push(shot);
+        }
+        enemy.doGravity();
+        let mut aabb = enemy.aabb();
+        let motion = enemy.vel;
+        let (_, walls) = bodyWalls(game, aabb, motion, false);
+        let before = aabb;
+        let hits = move_and_slide(&mut aabb, motion, &walls);
+        resolve_contacts(&mut aabb, &walls);
+        // enemies never jump, so they can always stick to a slope going down it
+        let on_slope = landOnSlopes(game, &mut aabb, before, motion, true);
+        let enemy = &mut game.enemies[i];
+        enemy.pos = aabb.center;
+        if on_slope || hits.iter().any(|hit| hit.normal.y != 0.0) {
+            enemy.vel.y = 0.0;
+        }
 
-                // println!("x vel: {}, y vel: {}", game.guy.vel.x , game.guy.vel.y );
+        // swinging at an enemy or landing on it knocks it out; running into one hurts
+        if attack_box.is_some_and(|hitbox| hitbox.contact(aabb).is_some()) {
+            enemy.hurt();
+        }
+        if enemy.is_alive() && !game.guy.is_dead() && guy_aabb.contact(aabb).is_some() {
+            if game.guy.vel.y < 0.0 && guy_aabb.center.y > aabb.center.y + aabb.size.y / 4.0 {
+                enemy.hurt();
+                game.guy.vel.y = 6.0;
+            } else if game.guy.hurt(aabb.center, enemy::ENEMY_DAMAGE) {
+                guy_hit = true;
             }
         }
     }
-    // Collision ------------------------------------------------------------------------
-}
 
-pub fn render_platformer(game: &mut Game, engine: &mut Engine) {
-    let DEMO_SPRITE_GROUP = 0;
-    let TILE_SPRITE_GROUP = 1;
+    // move the shots, dropping any that hit a wall or the guy
+    let mut shots = std::mem::take(&mut game.shots);
+    let mut shot_from = None;
+    let guy_dead = game.guy.is_dead();
+    shots.retain_mut(|shot| {
+        shot.age += 1;
+        if !guy_dead
+            && guy_aabb
+                .raycast(shot.pos, shot.vel, shot.vel.length())
+                .is_some()
+        {
+            shot_from = Some(shot.pos);
+            return false;
+        }
+        let next = shot.pos + shot.vel;
+        let walls = wallsAlong(game, shot.pos, next);
+        if segment_cast(shot.pos, next, &walls).is_some() {
+            return false;
+        }
+        shot.pos = next;
+        !shot.is_spent()
+    });
+    game.shots = shots;
+    if let Some(from) = shot_from {
+        guy_hit |= game.guy.hurt(from, enemy::ENEMY_DAMAGE);
+    }
+    guy_hit
+}
 
-    // [idx 0, idx 1..guy_idx, guy_idx, apple_start..)]
-    // [Background, walls..., guy, apples...]
+pub fn render_platformer(game: &mut Game, engine: &mut Engine, sprites: &mut PlatformerSprites) {
+    let camera = game.camera_effects.apply(game.follow_camera.camera());
+    let view = AABB {
+        center: Vec2::from(camera.screen_pos) + Vec2::from(camera.screen_size) / 2.0,
+        size: camera.screen_size.into(),
+    };
 
     // set bg image
-    let (trfs1, uvs1) = engine.renderer.sprites.get_sprites_mut(TILE_SPRITE_GROUP);
-    trfs1[0] = AABB::new(W / 2.0, H / 2.0, W, H).into(); // Create a non-collision AABB for use in the background
-    uvs1[0] = getSpriteFromSheet(TILE_SPRITE_GROUP as u16, &(0, 1), 10, TILE_SIZE);
-
-    // let (trfs2, uvs2) = engine.renderer.sprites.get_sprites_mut(TILE_SPRITE_GROUP);
-
-    // set walls
-    const WALL_START: usize = 1;
-    let guy_idx = WALL_START + game.collision_objects.len();
-    for (wall, (trf, uv)) in game.collision_objects.iter().zip(
-        trfs1[WALL_START..guy_idx]
-            .iter_mut()
-            .zip(uvs1[WALL_START..guy_idx].iter_mut()),
-    ) {
-        *trf = (wall.collision).into();
-        *uv = getSpriteFromSheet(TILE_SPRITE_GROUP as u16, &wall.tex_coord, 9, TILE_SIZE);
-    }
+    let (trf, uv) = engine.renderer.sprite_mut(&sprites.bg);
+    *trf = view.into(); // Create a non-collision AABB for use in the background
+    *uv = getSpriteFromSheet(
+        &game.tiles,
+        game.sprite_groups.tiles.index() as u16,
+        &(0, 1),
+        10,
+        TILE_SIZE,
+    );
 
-    if !matches!(game.mode, GameMode::Platformer) {
-        //println!("here render 1");
-        trfs1.fill(Transform::zeroed());
+    // set walls, only the ones on screen. There are slots for as many tiles as
+    // the view can touch, so this only reallocates when the zoom changes; the
+    // slots left over are blanked.
+    let across = ((view.size / TILE_WORLD_SIZE).ceil() + 1.0).max(Vec2::ZERO);
+    let most_visible = (across.x * across.y) as usize;
+    let slots = most_visible.min(game.collision_objects.len());
+    if sprites.walls.len() != slots {
+        engine.renderer.resize_sprites(&mut sprites.walls, slots);
+    }
+    let visible = game
+        .collision_objects
+        .iter()
+        .filter(|wall| view.contact(wall.collision).is_some());
+    let (trfs, uvs) = engine.renderer.sprites_mut(&sprites.walls);
+    trfs.fill(Transform::zeroed());
+    for (wall, (trf, uv)) in visible.zip(trfs.iter_mut().zip(uvs.iter_mut())) {
+        *trf = (wall.collision).into();
+        *uv = getSpriteFromSheet(
+            &game.tiles,
+            game.sprite_groups.tiles.index() as u16,
+            &wall.tex_coord,
+            9,
+            TILE_SIZE,
+        );
     }
 
-    let (trfs, uvs) = engine.renderer.sprites.get_sprites_mut(DEMO_SPRITE_GROUP);
-
-    // set guy
-    trfs[guy_idx] = AABB {
+    // set guy. Hurt and dying come from their own strips, so only one of his
+    // sprites is showing at a time.
+    let guy_box: Transform = AABB {
         center: game.guy.pos + 3.0,
         size: Vec2 { x: 32.0, y: 32.0 },
     }
     .into();
-    // animate guy
-    uvs[guy_idx] = getSpriteFromSheet_Demo(
-        DEMO_SPRITE_GROUP as u16,
-        GUY_FRAMES[game.guy.frame].0 + 24,
-        GUY_FRAMES[game.guy.frame].1 + 44,
-        8,
-        100,
-        100,
-    );
-
-    //uvs[guy_idx] = getSpriteFromSheet_Demo(DEMO_SPRITE_GROUP as u16, GUY_FRAMES[game.guy.frame].0, GUY_FRAMES[game.guy.frame].0, 8, 128, 130);
-    // SheetRegion::new(0, 16, 480, 8, 16, 16);
+    // flicker while he can't be hurt
+    let blink = game.guy.invulnerable > 0 && (game.guy.invulnerable / 4) % 2 == 1;
+    for sprite in sprites.guy.iter() {
+        let (trf, _uv) = engine.renderer.sprite_mut(sprite);
+        *trf = Transform::zeroed();
+    }
+    let texture = game.guy.anim.texture();
+    let (trf, uv) = engine.renderer.sprite_mut(&sprites.guy[texture]);
+    if !blink {
+        *trf = guy_box;
+    }
+    *uv = game
+        .guy
+        .anim
+        .sheet_region(game.sprite_groups.guy[texture].index() as u16, 8);
+
+    // set enemies and their shots. Every enemy has a slot in each animation group
+    // and shows up in the one for what it's doing; the rest stay blank.
+    let enemy_ranges = sprites.enemies.iter_mut().flatten();
+    let shot_ranges = sprites.shots.iter_mut();
+    for (range, len) in enemy_ranges
+        .map(|range| (range, game.enemies.len()))
+        .chain(shot_ranges.map(|range| (range, game.shots.len())))
+    {
+        if range.len() != len {
+            engine.renderer.resize_sprites(range, len);
+        }
+        let (trfs, _uvs) = engine.renderer.sprites_mut(range);
+        trfs.fill(Transform::zeroed());
+    }
+    for (i, enemy) in game.enemies.iter().enumerate() {
+        let range = &sprites.enemies[enemy::kindIndex(enemy.def.kind)][enemy.texture()];
+        let (trfs, uvs) = engine.renderer.sprites_mut(range);
+        trfs[i] = enemy.draw_box().into();
+        uvs[i] = enemy.sheet_region(&game.sprite_groups);
+    }
+    for (i, shot) in game.shots.iter().enumerate() {
+        let range = &sprites.shots[enemy::kindIndex(shot.kind)];
+        let (trfs, uvs) = engine.renderer.sprites_mut(range);
+        trfs[i] = AABB::new(shot.pos.x, shot.pos.y, 12.0, 12.0).into();
+        uvs[i] = shot.sheet_region(&game.sprite_groups);
+    }
 
-    if !matches!(game.mode, GameMode::Platformer) {
+    // set objects. Like the enemies, every object has a slot in each object group
+    // and shows up in its own; platforms get a slot of their own in the tile group.
+    let platforms: Vec<&Object> = game
+        .objects
+        .iter()
+        .filter(|object| object.texture().is_none())
+        .collect();
+    if sprites.platforms.len() != platforms.len() {
+        engine
+            .renderer
+            .resize_sprites(&mut sprites.platforms, platforms.len());
+    }
+    let (trfs, uvs) = engine.renderer.sprites_mut(&sprites.platforms);
+    for (platform, (trf, uv)) in platforms.iter().zip(trfs.iter_mut().zip(uvs.iter_mut())) {
+        *trf = platform.transform();
+        *uv = game.tiles.sheet_region(
+            game.sprite_groups.tiles.index() as u16,
+            &objects::PLATFORM_TILE,
+            9,
+            TILE_SIZE,
+        );
+    }
+    for range in sprites.objects.iter_mut() {
+        if range.len() != game.objects.len() {
+            engine.renderer.resize_sprites(range, game.objects.len());
+        }
+        let (trfs, _uvs) = engine.renderer.sprites_mut(range);
         trfs.fill(Transform::zeroed());
     }
+    for (i, object) in game.objects.iter().enumerate() {
+        let Some(texture) = object.texture() else {
+            continue;
+        };
+        let (trfs, uvs) = engine.renderer.sprites_mut(&sprites.objects[texture]);
+        trfs[i] = object.transform();
+        uvs[i] = object.sheet_region(&game.sprite_groups, 9);
+    }
 
-    engine.renderer.sprites.upload_sprites(
-        &engine.renderer.gpu,
-        DEMO_SPRITE_GROUP,
-        0..WALL_START + game.collision_objects.len() + 1,
-    );
-    engine.renderer.sprites.upload_sprites(
-        &engine.renderer.gpu,
-        TILE_SPRITE_GROUP,
-        0..game.collision_objects.len() + 1,
+    // SheetRegion::new(0, 16, 480, 8, 16, 16);
+
+    // set HUD
+    let (trf, uv) = engine.renderer.sprite_mut(&sprites.lives_icon);
+    *trf = screenBox(camera, 14.0, H - 12.0, 16.0, 16.0).into();
+    // the first idle frame, facing right
+    let (x, y, w, h) = game
+        .guy
+        .anim
+        .sheet()
+        .clip(AnimState::Idle)
+        .map_or((0, 0, 0, 0), |clip| clip.frames[0].rect);
+    *uv = SheetRegion::new(game.sprite_groups.guy[0].index() as u16, x, y, 1, w, h);
+    let text_box = screenBox(camera, 24.0, H - 16.0, 0.0, 8.0);
+    engine.renderer.draw_text(
+        &sprites.font,
+        &sprites.lives,
+        &game.guy.lives.to_string(),
+        text_box.center,
+        text_box.size.y,
     );
-    engine
-        .renderer
-        .sprites
-        .set_camera_all(&engine.renderer.gpu, game.camera);
+    let (trfs, uvs) = engine.renderer.sprites_mut(&sprites.health);
+    for (i, (trf, uv)) in trfs.iter_mut().zip(uvs.iter_mut()).enumerate() {
+        *trf = if i < game.guy.health as usize {
+            screenBox(camera, W - 12.0 - 14.0 * i as f32, H - 12.0, 12.0, 12.0).into()
+        } else {
+            Transform::zeroed()
+        };
+        *uv = SheetRegion::new(game.sprite_groups.hud.index() as u16, 0, 496, 1, 16, 16);
+    }
+
+    engine.renderer.set_camera_all(camera);
 }
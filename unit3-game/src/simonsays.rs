@@ -8,19 +8,17 @@ use std::f32::RADIX;
 // use std::os::windows::fs::FileTypeExt;
 use std::fs::read_to_string;
 use std::path::Path;
+use std::time;
 use std::time::Duration;
-use std::{thread, time};
 
-use crate::{getSpriteFromSheet, getSpriteFromSheet_Demo, Game, GameMode, SpriteTile};
+use engine_simple::{Callback, Scene, Scheduler, Sprite, SpriteGroup, SpriteRange, Transition};
+
+use crate::{getSpriteFromSheet, getSpriteFromSheet_Demo, playSfx, Game, SpriteTile};
 
 const W: f32 = 320.0;
 const H: f32 = 240.0;
 
 const TILE_SIZE: u16 = 256;
-#[cfg(target_os = "macos")]
-const SCALING_FACTOR: f32 = 5.0;
-#[cfg(target_os = "windows")]
-const SCALING_FACTOR: f32 = 2.5;
 const PATTERN_DELAY: Duration = time::Duration::from_millis(500);
 
 pub struct SimonSaysState {
@@ -52,7 +50,7 @@ fn newSpriteTile_Rect(
     }
 }
 
-pub fn initialize() -> SimonSaysState {
+pub fn initialize(rng: &mut impl Rng) -> SimonSaysState {
     let mut knobs = Vec::default();
     knobs.push((newSpriteTile_Square(W / 4.0, H / 2.0, H / 4.0, 2, 0), 0.0)); // Left
     knobs.push((
@@ -66,8 +64,8 @@ pub fn initialize() -> SimonSaysState {
     knobs.push((newSpriteTile_Square(W / 2.0, H / 4.0, H / 4.0, 2, 0), 0.0)); // Bottom
 
     let mut pattern = Vec::default();
-    pattern.push(rand::thread_rng().gen_range(0..=3));
-    pattern.push(rand::thread_rng().gen_range(0..=3));
+    pattern.push(rng.gen_range(0..=3));
+    pattern.push(rng.gen_range(0..=3));
 
     SimonSaysState {
         knobs,
@@ -78,34 +76,120 @@ pub fn initialize() -> SimonSaysState {
     }
 }
 
-pub fn update_simon_says(game: &mut Game, engine: &mut Engine) {
+pub struct SimonSaysSprites {
+    bg: Sprite,
+    knobs: SpriteRange,
+}
+
+pub struct SimonSaysScene {
+    sprites: SimonSaysSprites,
+    groups: Vec<SpriteGroup>,
+    scheduler: Scheduler<Game>, // paces the pattern playback
+}
+
+impl SimonSaysScene {
+    pub fn new(game: &Game, engine: &mut Engine) -> Self {
+        let group = game.sprite_groups.simon_says;
+        let sprites = SimonSaysSprites {
+            bg: engine.renderer.alloc_sprite(group),
+            knobs: engine.renderer.alloc_sprites(group, 0),
+        };
+        Self {
+            sprites,
+            groups: vec![group],
+            scheduler: Scheduler::default(),
+        }
+    }
+}
+
+impl Scene<Game> for SimonSaysScene {
+    fn on_enter(&mut self, game: &mut Game, engine: &mut Engine) {
+        if !game.simon_says.awaitInput {
+            playPattern(game, &mut self.scheduler, engine);
+        }
+    }
+
+    fn update(&mut self, game: &mut Game, engine: &mut Engine) -> Transition<Game> {
+        self.scheduler.update(game, engine);
+        update_simon_says(game, engine, &mut self.scheduler)
+    }
+
+    fn render(&mut self, game: &mut Game, engine: &mut Engine) {
+        render_simon_says(game, engine, &mut self.sprites)
+    }
+
+    fn sprite_groups(&self) -> &[SpriteGroup] {
+        &self.groups
+    }
+}
+
+// Show the pattern one knob every PATTERN_DELAY, then wait for the player to repeat it
+fn playPattern(game: &mut Game, scheduler: &mut Scheduler<Game>, engine: &Engine) {
+    game.simon_says.pattern_counter = 0;
+    game.simon_says.awaitInput = false;
+    let last = game.simon_says.pattern.len() - 1;
+    let steps = game
+        .simon_says
+        .pattern
+        .iter()
+        .enumerate()
+        .map(|(i, &knob)| {
+            let step: Callback<Game> = Box::new(
+                move |game: &mut Game, _: &mut Scheduler<Game>, _: &mut Engine| {
+                    showKnob(game, knob);
+                    if i == last {
+                        game.simon_says.awaitInput = true;
+                    }
+                },
+            );
+            (PATTERN_DELAY, step)
+        });
+    scheduler.sequence(engine, steps);
+}
+
+fn showKnob(game: &mut Game, knob: usize) {
+    game.simon_says.knobs[knob].1 += PI / 4.0;
+    println!("pattern num: {}", knob);
+    match knob {
+        0 => {
+            playSfx(game, 3);
+        }
+        1 => {
+            playSfx(game, 4);
+        }
+        2 => {
+            playSfx(game, 5);
+        }
+        3 => {
+            playSfx(game, 8);
+        }
+        _ => (),
+    }
+}
+
+pub fn update_simon_says(
+    game: &mut Game,
+    engine: &mut Engine,
+    scheduler: &mut Scheduler<Game>,
+) -> Transition<Game> {
     if game.simon_says.awaitInput {
         if engine
             .input
             .is_mouse_pressed(winit::event::MouseButton::Left)
         {
-            let window_height = engine.renderer.gpu.config.height as f32;
-
-            let mouse_pos = engine.input.mouse_pos();
-            // Normalize mouse clicks to be 00 at bottom left corner
-            let (x_norm, y_norm) = (
-                (mouse_pos.x as f32 + game.camera.screen_pos[0]) / SCALING_FACTOR,
-                (((mouse_pos.y as f32 - window_height) * (-1.0 as f32))
-                    + game.camera.screen_pos[1])
-                    / SCALING_FACTOR,
-            );
-
-            println!(
-                "MOUSE: {}, {}\nNORM: {}, {}",
-                mouse_pos.x, mouse_pos.y, x_norm, y_norm
-            );
+            // Convert the click into world coordinates, 0,0 at bottom left corner
+            let mouse_world = engine.mouse_world_pos();
             let mut doRestart = false;
             let mut finishedPattern = false;
 
-            println!("{}, {}, x{}, y{}", mouse_pos.x, mouse_pos.y, x_norm, y_norm);
-
             for (idx, ss_object) in game.simon_says.knobs.iter_mut().enumerate() {
-                if (ss_object.0.collision.contains(x_norm, y_norm)) {
+                // knobs turn as they're clicked, so test against the turned box
+                if ss_object
+                    .0
+                    .collision
+                    .rotated(ss_object.1)
+                    .contains(mouse_world)
+                {
                     // Clicked on a knob
                     if idx == game.simon_says.pattern[game.simon_says.pattern_counter] {
                         // Clicked on the correct knob, continue
@@ -113,16 +197,16 @@ pub fn update_simon_says(game: &mut Game, engine: &mut Engine) {
 
                         match game.simon_says.pattern[game.simon_says.pattern_counter] {
                             0 => {
-                                let _ = game.sfx_manager.play(game.sfx[3].clone());
+                                playSfx(game, 3);
                             }
                             1 => {
-                                let _ = game.sfx_manager.play(game.sfx[4].clone());
+                                playSfx(game, 4);
                             }
                             2 => {
-                                let _ = game.sfx_manager.play(game.sfx[5].clone());
+                                playSfx(game, 5);
                             }
                             3 => {
-                                let _ = game.sfx_manager.play(game.sfx[8].clone());
+                                playSfx(game, 8);
                             }
                             _ => (),
                         }
@@ -152,115 +236,58 @@ pub fn update_simon_says(game: &mut Game, engine: &mut Engine) {
                     game.simon_says.pattern.pop();
                 }
 
-                game.simon_says.pattern_counter = 0;
-                game.simon_says.awaitInput = false;
+                playPattern(game, scheduler, engine);
             } else if finishedPattern {
                 if game.simon_says.pattern_counter > 5 {
                     game.simon_says.completed = true;
-                    game.mode = GameMode::Platformer;
-                    render_simon_says(game, engine);
-                    return;
+                    return Transition::Pop;
                 }
-                game.simon_says
-                    .pattern
-                    .push(rand::thread_rng().gen_range(0..=3));
-                // thread::sleep(PATTERN_DELAY);
-                game.simon_says.pattern_counter = 0;
-                game.simon_says.awaitInput = false;
+                game.simon_says.pattern.push(engine.rng.gen_range(0..=3));
+                playPattern(game, scheduler, engine);
             }
         }
-    } else {
-        //Perform the pattern
-
-        thread::sleep(PATTERN_DELAY);
-        game.simon_says.knobs[game.simon_says.pattern[game.simon_says.pattern_counter]].1 +=
-            PI / 4.0;
-        println!(
-            "pattern num: {}",
-            game.simon_says.pattern[game.simon_says.pattern_counter]
-        );
-        match game.simon_says.pattern[game.simon_says.pattern_counter] {
-            0 => {
-                let _ = game.sfx_manager.play(game.sfx[3].clone());
-            }
-            1 => {
-                let _ = game.sfx_manager.play(game.sfx[4].clone());
-            }
-            2 => {
-                let _ = game.sfx_manager.play(game.sfx[5].clone());
-            }
-            3 => {
-                let _ = game.sfx_manager.play(game.sfx[8].clone());
-            }
-            _ => (),
-        }
-        game.simon_says.pattern_counter += 1;
-
-        if (game.simon_says.pattern_counter >= game.simon_says.pattern.len()) {
-            game.simon_says.awaitInput = true;
-            game.simon_says.pattern_counter = 0;
-        }
     }
 
     if engine.input.is_key_pressed(engine::Key::S) {
-        game.mode = GameMode::Platformer;
-        if !matches!(game.mode, GameMode::SimonSays) {
-            println!("here");
-            render_simon_says(game, engine)
-        }
+        return Transition::Pop;
     }
+    Transition::None
 }
 
-pub fn render_simon_says(game: &mut Game, engine: &mut Engine) {
-    let DEMO_SPRITE_GROUP = 0;
-    let TILE_SPRITE_GROUP = 1;
-    let SIMON_SAYS_SPRITE_GROUP = 2;
-
-    // [idx 0, idx 1..guy_idx, guy_idx, apple_start..)]
-    // [Background, walls..., guy, apples...]
-
+pub fn render_simon_says(game: &mut Game, engine: &mut Engine, sprites: &mut SimonSaysSprites) {
     // set bg image
-    let (trfs1, uvs1) = engine
-        .renderer
-        .sprites
-        .get_sprites_mut(SIMON_SAYS_SPRITE_GROUP);
-    trfs1[0] = AABB::new(W / 2.0, H / 2.0, W, H).into(); // Create a non-collision AABB for use in the background
-    uvs1[0] = getSpriteFromSheet(TILE_SPRITE_GROUP as u16, &(3, 0), 12, TILE_SIZE);
-
-    // let (trfs2, uvs2) = engine.renderer.sprites.get_sprites_mut(TILE_SPRITE_GROUP);
-
-    // set walls
-    const WALL_START: usize = 1;
-
-    for (ss_object, (trf, uv)) in game.simon_says.knobs.iter().zip(
-        trfs1[WALL_START..]
-            .iter_mut()
-            .zip(uvs1[WALL_START..].iter_mut()),
-    ) {
+    let (trf, uv) = engine.renderer.sprite_mut(&sprites.bg);
+    *trf = AABB::new(W / 2.0, H / 2.0, W, H).into(); // Create a non-collision AABB for use in the background
+    *uv = getSpriteFromSheet(
+        &game.tiles,
+        game.sprite_groups.tiles.index() as u16,
+        &(3, 0),
+        12,
+        TILE_SIZE,
+    );
+
+    // set knobs
+    if sprites.knobs.len() != game.simon_says.knobs.len() {
+        engine
+            .renderer
+            .resize_sprites(&mut sprites.knobs, game.simon_says.knobs.len());
+    }
+    let (trfs, uvs) = engine.renderer.sprites_mut(&sprites.knobs);
+    for (ss_object, (trf, uv)) in game
+        .simon_says
+        .knobs
+        .iter()
+        .zip(trfs.iter_mut().zip(uvs.iter_mut()))
+    {
         *trf = ss_object.0.collision.to_transform_rot(ss_object.1);
         *uv = getSpriteFromSheet(
-            TILE_SPRITE_GROUP as u16,
+            &game.tiles,
+            game.sprite_groups.tiles.index() as u16,
             &ss_object.0.tex_coord,
             11,
             TILE_SIZE,
         );
     }
 
-    if !matches!(game.mode, GameMode::SimonSays) {
-        trfs1.fill(Transform::zeroed());
-    }
-
-    engine.renderer.sprites.upload_sprites(
-        &engine.renderer.gpu,
-        SIMON_SAYS_SPRITE_GROUP,
-        0..WALL_START + game.simon_says.knobs.len() + 1,
-    );
-    // engine
-    //     .renderer
-    //     .sprites
-    //     .upload_sprites(&engine.renderer.gpu, SIMON_SAYS_SPRITE_GROUP, 0..0);
-    engine
-        .renderer
-        .sprites
-        .set_camera_all(&engine.renderer.gpu, game.camera);
+    engine.renderer.set_camera_all(game.camera);
 }
@@ -0,0 +1,109 @@
+use engine_simple::AssetError;
+use engine_simple::SheetRegion;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::path::Path;
+
+pub const TILESET_PATH: &str = "tileset.ron";
+// the sheet the tileset's tiles are drawn from
+pub const SHEET_PATH: &str = "new_spritesheet.png";
+
+// Which part of a tile is solid
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize)]
+pub enum Collision {
+    #[default]
+    Full,
+    TopHalf,
+    BottomHalf,
+    None,
+    // a floor running from (left, right) of the way up the tile at each edge:
+    // Slope(0.0, 1.0) is a 45 degree ramp up to the right, and Slope(0.0, 0.5)
+    // then Slope(0.5, 1.0) a shallower one over two tiles. It can be walked up
+    // onto from either side and jumped up through from below.
+    Slope(f32, f32),
+}
+
+impl Collision {
+    // For slopes, how high the floor is `t` of the way across the tile (0 at
+    // the left edge, 1 at the right), as a share of the tile's height
+    pub fn floor_at(&self, t: f32) -> Option<f32> {
+        match self {
+            Collision::Slope(left, right) => Some(left + (right - left) * t.clamp(0.0, 1.0)),
+            _ => None,
+        }
+    }
+}
+
+// Which part of a tile gets drawn
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize)]
+pub enum RenderSize {
+    #[default]
+    Full,
+    TopHalf,
+    BottomHalf,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+pub enum HazardKind {
+    Spikes,
+    Acid,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    // how much health touching it costs
+    pub damage: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TileProps {
+    pub collision: Collision,
+    // only its top is solid, and only from above: it can be jumped up through,
+    // and dropped through by holding down
+    pub one_way: bool,
+    pub hazard: Option<Hazard>,
+    pub door: bool,
+    pub render: RenderSize,
+}
+
+// Properties of every tile on a sheet, keyed by sheet coordinate
+#[derive(Default, Debug)]
+pub struct Tileset {
+    tiles: HashMap<(u16, u16), TileProps>,
+}
+
+impl Tileset {
+    pub fn load(path: &Path) -> Result<Tileset, AssetError> {
+        let text = read_to_string(path).map_err(|err| AssetError::Io(path.to_path_buf(), err))?;
+        let tiles = ron::from_str(&text)
+            .map_err(|err| AssetError::Parse(path.to_path_buf(), err.to_string()))?;
+        Ok(Tileset { tiles })
+    }
+
+    // Tiles that aren't in the file are solid full-size blocks
+    pub fn get(&self, tex_coord: &(u16, u16)) -> TileProps {
+        self.tiles.get(tex_coord).copied().unwrap_or_default()
+    }
+
+    pub fn sheet_region(
+        &self,
+        sheet_num: u16,
+        tex_coord: &(u16, u16),
+        depth: u16,
+        sprite_size: u16,
+    ) -> SheetRegion {
+        let x = tex_coord.0 * sprite_size;
+        let y = tex_coord.1 * sprite_size;
+        let half = sprite_size / 2;
+        match self.get(tex_coord).render {
+            RenderSize::Full => SheetRegion::new(sheet_num, x, y, depth, sprite_size, sprite_size),
+            RenderSize::TopHalf => SheetRegion::new(sheet_num, x, y, depth, sprite_size, half),
+            RenderSize::BottomHalf => {
+                SheetRegion::new(sheet_num, x, y + half, depth, sprite_size, half)
+            }
+        }
+    }
+}